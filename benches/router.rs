@@ -0,0 +1,71 @@
+//! Measures the per request overhead of routing with `PathRouter`.
+//!
+//! Run with `cargo bench --bench router`.
+use std::{
+    hint::black_box,
+    time::Instant,
+};
+
+use tower::{Service, ServiceExt};
+use wayfinder::{
+    extract::{Capture, State},
+    server::{methods, PathRouter},
+    Body, Request,
+};
+
+const ITERATIONS: u32 = 200_000;
+
+fn router() -> PathRouter {
+    let mut router = PathRouter::default();
+    for i in 0..50 {
+        router = router
+            .route(format!("/static/{i}"), methods::get(|| async { "static" }))
+            .route(format!("/users/{i}/:id"), methods::get(|Capture(id): Capture<String>| async move { id }));
+    }
+    router
+        .route("/", methods::get(|| async { "index" }).post(|| async { "created" }))
+        .route("/layered", methods::get(|| async { "layered" }))
+        .route_layer(tower::layer::util::Identity::new())
+        .route("/state", methods::get(|State(name): State<String>| async move { name }))
+        .with_state("state".to_string())
+}
+
+fn request(uri: &str) -> Request {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+async fn bench(name: &str, router: &PathRouter, uri: &str) {
+    let mut service = router.clone();
+
+    // Warm up
+    for _ in 0..1_000 {
+        black_box(ServiceExt::<Request>::ready(&mut service).await.unwrap().call(request(uri)).await.unwrap());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(ServiceExt::<Request>::ready(&mut service).await.unwrap().call(request(uri)).await.unwrap());
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:<12} {:>8.0} ns/request",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64,
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let router = router();
+        bench("index", &router, "/").await;
+        bench("static", &router, "/static/25").await;
+        bench("capture", &router, "/users/25/abc").await;
+        bench("layered", &router, "/layered").await;
+        bench("state", &router, "/state").await;
+        bench("not found", &router, "/missing/path").await;
+    });
+}
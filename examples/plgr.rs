@@ -5,17 +5,19 @@ use wayfinder::{
     extract::{Capture, Cookie, CookieJar, File, Form, Json, Multipart, Query, TempFile, UrlEncoded},
     layer::LogLayer,
     prelude::*,
-    server::{methods, FileRouter, PathRouter, Server, LOCAL, NETWORK},
+    server::{methods, FileRouter, PathRouter, Server, NETWORK},
     Result, StatusCode
 };
 
 async fn home(jar: CookieJar) -> impl IntoResponse {
-    for cookie in jar.as_ref().iter() {
-        println!("{} = {}", cookie.name(), cookie.value())
-    }
+    jar.with(|jar| {
+        for cookie in jar.iter() {
+            println!("{} = {}", cookie.name(), cookie.value())
+        }
+    });
 
-    if jar.as_ref().get("last_wayfinder_page").is_none() {
-        jar.as_mut().add(Cookie::new("last_wayfinder_page", "home"));
+    if jar.get("last_wayfinder_page").is_none() {
+        jar.add(Cookie::new("last_wayfinder_page", "home"));
     }
 
     File::open("index.html").await.unwrap()
@@ -85,5 +87,6 @@ fn main() -> Result<()> {
                 .layer(LogLayer::new("Wayfinder", None))
                 .into_service(),
         )
+        .log_routes()
         .run()
 }
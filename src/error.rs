@@ -0,0 +1,196 @@
+use std::{borrow::Cow, fmt::Display, sync::Arc};
+
+use hyper::{header::{HeaderName, HeaderValue}, HeaderMap, StatusCode};
+
+use crate::{extract::{IntoResponse, PublicMessage, ResponseError}, Body, Response};
+
+/// An error with the status it should be answered with.
+///
+/// The message is sent to the client, the source is only logged and kept for catchers. Like
+/// extractor rejections the response has an empty body, unless the router renders one with
+/// [`PathRouter::rejection_body`](crate::server::PathRouter::rejection_body).
+///
+/// ```ignore
+/// async fn user(Capture(id): Capture<u32>) -> Result<Json<User>, HttpError> {
+///     let user = std::fs::read_to_string(format!("users/{id}.json"))?;
+///     Ok(Json(serde_json::from_str(&user)?))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HttpError {
+    status: StatusCode,
+    message: Option<Cow<'static, str>>,
+    source: Option<crate::Error>,
+    headers: HeaderMap,
+}
+
+impl HttpError {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            message: None,
+            source: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// `400 Bad Request`
+    pub fn bad_request() -> Self {
+        Self::new(StatusCode::BAD_REQUEST)
+    }
+
+    /// `401 Unauthorized`
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED)
+    }
+
+    /// `403 Forbidden`
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::FORBIDDEN)
+    }
+
+    /// `404 Not Found`
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND)
+    }
+
+    /// `409 Conflict`
+    pub fn conflict() -> Self {
+        Self::new(StatusCode::CONFLICT)
+    }
+
+    /// `422 Unprocessable Entity`
+    pub fn unprocessable() -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY)
+    }
+
+    /// `500 Internal Server Error`
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Message that is safe to show to the client
+    pub fn with_message<M: Into<Cow<'static, str>>>(mut self, message: M) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// The error that caused this one, it is logged but never sent to the client
+    pub fn with_source<E: Into<crate::Error>>(mut self, source: E) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Add a header to the response, like `WWW-Authenticate` or `Retry-After`
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Unwrap an `HttpError` that was boxed, the error is given back otherwise. Other errors
+    /// aren't classified since an internal `io::Error` or `serde_json::Error` isn't the client's
+    /// fault, convert them with `?` where they are about the request.
+    pub(crate) fn classify(error: crate::Error) -> Result<Self, crate::Error> {
+        error.downcast::<HttpError>().map(|error| *error)
+    }
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.message, &self.source) {
+            (Some(message), _) => f.write_str(message),
+            (None, Some(source)) => source.fmt(f),
+            (None, None) => f.write_str(self.status.canonical_reason().unwrap_or("Unknown error")),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(mut self) -> Response {
+        if self.status.is_server_error() {
+            // Without a message the error displays as it's source already
+            match (&self.message, &self.source) {
+                (Some(message), Some(source)) => log::error!("{}: {}: {}", self.status, message, source),
+                _ => log::error!("{}: {}", self.status, self),
+            }
+        } else {
+            log::debug!("Responding with {}: {}", self.status, self);
+        }
+
+        let message = self.message.clone().map(|message| PublicMessage(message.into_owned()));
+        let headers = std::mem::take(&mut self.headers);
+        let mut response = Response::builder()
+            .status(self.status)
+            .body(Body::empty())
+            .unwrap();
+        response.headers_mut().extend(headers);
+        if let Some(message) = message {
+            response.extensions_mut().insert(message);
+        }
+        response.extensions_mut().insert(ResponseError(Arc::new(Box::new(self))));
+        response
+    }
+}
+
+impl From<StatusCode> for HttpError {
+    fn from(status: StatusCode) -> Self {
+        Self::new(status)
+    }
+}
+
+/// Missing files are `404 Not Found` and files that can't be accessed are `403 Forbidden`
+impl From<std::io::Error> for HttpError {
+    fn from(error: std::io::Error) -> Self {
+        let status = match error.kind() {
+            std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            std::io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status).with_source(error)
+    }
+}
+
+/// Json that doesn't fit the expected data is `422 Unprocessable Entity`
+impl From<serde_json::Error> for HttpError {
+    fn from(error: serde_json::Error) -> Self {
+        let status = match error.classify() {
+            serde_json::error::Category::Io => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        Self::new(status).with_source(error)
+    }
+}
+
+impl From<serde_urlencoded::de::Error> for HttpError {
+    fn from(error: serde_urlencoded::de::Error) -> Self {
+        Self::unprocessable().with_source(error)
+    }
+}
+
+/// A boxed `HttpError` keeps its status, everything else is a `500 Internal Server Error`
+impl From<crate::Error> for HttpError {
+    fn from(error: crate::Error) -> Self {
+        match Self::classify(error) {
+            Ok(error) => error,
+            Err(error) => Self::internal().with_source(error),
+        }
+    }
+}
@@ -67,9 +67,34 @@ impl MissingParameters<usize> {
     }
 }
 
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::MissingParameters { from_pattern, from_extractor } => write!(
+                f,
+                "the route has {} captures but {} were expected",
+                from_pattern,
+                from_extractor
+            ),
+            ErrorKind::ParseError { key: ParseErrorKey::Key(key), value, expected } => {
+                write!(f, "can't parse `{}` as `{}` for `{}`", value, expected, key)
+            },
+            ErrorKind::ParseError { key: ParseErrorKey::Index(index), value, expected } => {
+                write!(f, "can't parse `{}` as `{}` for capture {}", value, expected, index)
+            },
+            ErrorKind::ParseError { key: ParseErrorKey::None, value, expected } => {
+                write!(f, "can't parse `{}` as `{}`", value, expected)
+            },
+            ErrorKind::InvalidEncoding(key) => write!(f, "`{}` is not valid utf-8", key),
+            ErrorKind::UnsupportedType(name) => write!(f, "`{}` can't be captured from a path", name),
+            ErrorKind::Other(message) => f.write_str(message),
+        }
+    }
+}
+
 impl std::fmt::Display for PathDeserializationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.kind.fmt(f)
+        std::fmt::Display::fmt(&self.kind, f)
     }
 }
 
@@ -1,12 +1,12 @@
-use std::{fmt::Display, sync::Arc};
+use std::sync::Arc;
 
 use hyper::http::request::Parts;
 use serde::de::DeserializeOwned;
-use crate::{Error, PercentDecodedStr};
+use crate::PercentDecodedStr;
 
 mod de;
 
-use super::{request::FromParts, CookieJar};
+use super::{rejection::PathRejection, request::{FromParts, OptionalFromParts}, CookieJar};
 use de::{ErrorKind, PathDeserializationError, PathDeserializer};
 
 #[derive(Debug, Clone)]
@@ -15,34 +15,47 @@ pub enum UriParams {
     InvalidEncoding(Arc<str>)
 }
 
-#[derive(Debug)]
-pub struct MissingPathParams;
-impl Display for MissingPathParams {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Missing URI Path Params")
-    }
-}
-impl std::error::Error for MissingPathParams {}
-
 pub struct Capture<T>(pub T);
 
 impl<T> FromParts for Capture<T>
 where
     T: DeserializeOwned + Send,
 {
-    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Error> {
+    type Rejection = PathRejection;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
         let params = match parts.extensions.get::<UriParams>() {
             Some(UriParams::Valid(captures)) => captures,
-            Some(UriParams::InvalidEncoding(key)) => {
-                return Err(PathDeserializationError {
-                    kind: ErrorKind::InvalidEncoding(key.to_string())
-                }.into())
-            },
-            None => {
-                return Err(MissingPathParams.into())
-            }
+            Some(UriParams::InvalidEncoding(key)) => return Err(PathRejection::InvalidEncoding(key.to_string())),
+            None => return Err(PathRejection::MissingParams),
         };
 
         Ok(T::deserialize(PathDeserializer::new(params)).map(Capture)?)
     }
 }
+
+/// `None` when the route has no captures
+impl<T> OptionalFromParts for Capture<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = PathRejection;
+
+    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        match parts.extensions.get::<UriParams>() {
+            Some(UriParams::Valid(captures)) if captures.is_empty() => Ok(None),
+            None => Ok(None),
+            _ => <Self as FromParts>::from_parts(parts, jar).await.map(Some),
+        }
+    }
+}
+
+impl From<PathDeserializationError> for PathRejection {
+    fn from(error: PathDeserializationError) -> Self {
+        match error.kind {
+            ErrorKind::InvalidEncoding(key) => PathRejection::InvalidEncoding(key),
+            ErrorKind::ParseError { .. } | ErrorKind::Other(_) => PathRejection::Invalid(error.to_string()),
+            ErrorKind::MissingParameters { .. } | ErrorKind::UnsupportedType(_) => PathRejection::Mismatch(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{convert::Infallible, fmt::Display, sync::Arc};
+
+use hyper::{http::request::Parts, HeaderMap, StatusCode};
+
+use crate::{Error, Response};
+
+use super::{rejection::reject, request::{FromParts, OptionalFromParts}, CookieJar, IntoResponse};
+
+#[derive(Debug)]
+pub struct NotCaught;
+impl Display for NotCaught {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No caught response; the handler was not called as a catcher")
+    }
+}
+impl std::error::Error for NotCaught {}
+
+impl IntoResponse for NotCaught {
+    fn into_response(self) -> Response {
+        reject(StatusCode::INTERNAL_SERVER_ERROR, self)
+    }
+}
+
+/// The error response that a catcher is handling.
+///
+/// Only available in handlers registered with [`PathRouter::catch`](crate::server::PathRouter::catch).
+#[derive(Clone)]
+pub struct Caught {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) error: Option<Arc<Error>>,
+}
+
+impl Caught {
+    /// Status of the original response
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Headers of the original response
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The error that caused the response, if the response was created from an error
+    pub fn error(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.error.as_deref().map(|error| error.as_ref())
+    }
+}
+
+impl std::fmt::Debug for Caught {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Caught")
+            .field("status", &self.status)
+            .field("error", &self.error.as_ref().map(|e| e.to_string()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromParts for Caught {
+    type Rejection = NotCaught;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        parts.extensions
+            .get::<Caught>()
+            .cloned()
+            .ok_or(NotCaught)
+    }
+}
+
+impl OptionalFromParts for Caught {
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Caught>().cloned())
+    }
+}
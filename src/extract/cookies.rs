@@ -1,37 +1,411 @@
-use hyper::{header, http::request::Parts};
-use std::{cell::{Ref, RefCell, RefMut}, sync::Arc};
+use hyper::{header::{self, HeaderValue}, http::request::Parts};
+use std::{any::type_name, convert::Infallible, sync::{Arc, Mutex, MutexGuard}};
 
-use crate::Error;
+use crate::Response;
 
-use super::request::FromParts;
+use super::{request::FromParts, IntoResponseParts, MissingState, shared};
 
 #[allow(unused_imports)]
-pub use cookie::{Cookie, PrivateJar, SignedJar};
+pub use cookie::{Cookie, Key, PrivateJar, SignedJar};
 
-/// [cookie::CookieJar]
-//pub type CookieJar = Arc<Mutex<cookie::CookieJar>>;
+/// The cookies of a request and the changes to send back, shared by every extractor of a handler.
+///
+/// Changed cookies are sent as a `Set-Cookie` header each once the handler returns. Middleware can
+/// share the jar with the handler by adding one to the request extensions before calling it, and
+/// then add its own changes to the response with [`IntoResponseParts`].
+///
+/// ```ignore
+/// let jar = CookieJar::default();
+/// request.extensions_mut().insert(jar.clone());
+/// let response = inner.call(request).await?;
+/// jar.add(Cookie::new("seen", "1"));
+/// jar.into_response_parts(response)
+/// ```
 #[derive(Default, Clone)]
-pub struct CookieJar(Arc<RefCell<cookie::CookieJar>>);
+pub struct CookieJar(Arc<Mutex<cookie::CookieJar>>);
 impl CookieJar {
-    // add, get, remove, remove_force, add_original, iter
-    pub fn as_ref(&self) -> Ref<'_, cookie::CookieJar> {
-        self.0.borrow()
+    /// Use the underlying [cookie::CookieJar]. The jar is locked while the closure runs, so it
+    /// can't be held across an `.await`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut cookie::CookieJar) -> R) -> R {
+        f(&mut self.lock())
     }
 
-    pub fn as_mut(&self) -> RefMut<'_, cookie::CookieJar> {
-        self.0.borrow_mut()
+    pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+        self.lock().get(name).cloned()
+    }
+
+    pub fn add<C: Into<Cookie<'static>>>(&self, cookie: C) {
+        self.lock().add(cookie);
+    }
+
+    /// Remove a cookie from the client, the path and domain have to match the ones it was set with
+    pub fn remove<C: Into<Cookie<'static>>>(&self, cookie: C) {
+        self.lock().remove(cookie);
+    }
+
+    /// A panic while the jar was locked doesn't leave it in a broken state
+    fn lock(&self) -> MutexGuard<'_, cookie::CookieJar> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
     }
 }
-unsafe impl Send for CookieJar {}
 
 impl FromParts for CookieJar {
-    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Error> {
-        if let Some(cookies) = parts.headers.get(header::COOKIE) {
-            let mut jar = jar.as_mut();
-            for cookie in Cookie::split_parse_encoded(cookies.to_str().unwrap().to_string()).flatten() {
-                jar.add_original(cookie);
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        let mut inner = jar.lock();
+        // Cookies that aren't valid are ignored, they might come from another application
+        for cookies in parts.headers.get_all(header::COOKIE).iter().filter_map(|v| v.to_str().ok()) {
+            for cookie in Cookie::split_parse_encoded(cookies.to_string()).flatten() {
+                inner.add_original(cookie);
             }
         }
+        drop(inner);
         Ok(jar)
     }
 }
+
+/// Add a `Set-Cookie` header for every changed cookie. The changes are only sent once, so the
+/// jar can keep being used afterwards.
+impl IntoResponseParts for CookieJar {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: Response) -> Result<Response, Self::Error> {
+        let mut jar = self.lock();
+        for cookie in jar.delta() {
+            set_cookie(&mut res, cookie);
+        }
+        jar.reset_delta();
+        drop(jar);
+        Ok(res)
+    }
+}
+
+impl IntoResponseParts for Cookie<'static> {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: Response) -> Result<Response, Self::Error> {
+        set_cookie(&mut res, &self);
+        Ok(res)
+    }
+}
+
+/// Every cookie needs it's own header, joined cookies are read as one cookie with attributes
+fn set_cookie(res: &mut Response, cookie: &Cookie<'_>) {
+    match HeaderValue::try_from(cookie.encoded().to_string()) {
+        Ok(value) => {
+            res.headers_mut().append(header::SET_COOKIE, value);
+        },
+        Err(e) => log::error!("Failed to set cookie `{}`: {}", cookie.name(), e),
+    }
+}
+
+/// Keys used by [`SignedCookies`] and [`PrivateCookies`].
+///
+/// The keys are looked up as their own state, so add them with
+/// [`PathRouter::with_state`](crate::server::PathRouter::with_state), or derive them from the
+/// application state with [`FromRef`](super::FromRef) and
+/// [`PathRouter::with_substate`](crate::server::PathRouter::with_substate). Keys that are only a
+/// field of another state aren't found.
+///
+/// New cookies are signed or encrypted with the current key. Cookies from older keys are still
+/// accepted, so keys can be rotated without logging everyone out.
+///
+/// ```ignore
+/// PathRouter::default()
+///     .with_state(CookieKeys::new(Key::derive_from(&current)).with_previous(Key::derive_from(&old)))
+///
+/// impl FromRef<AppState> for CookieKeys {
+///     fn from_ref(state: &AppState) -> Self { state.keys.clone() }
+/// }
+///
+/// PathRouter::default()
+///     .with_state(AppState { keys, .. })
+///     .with_substate::<AppState, CookieKeys>()
+/// ```
+#[derive(Clone)]
+pub struct CookieKeys {
+    keys: Arc<[Key]>,
+}
+
+impl CookieKeys {
+    pub fn new(key: Key) -> Self {
+        Self { keys: Arc::from([key]) }
+    }
+
+    /// Also accept cookies from an older key
+    pub fn with_previous(self, key: Key) -> Self {
+        let mut keys = self.keys.to_vec();
+        keys.push(key);
+        Self { keys: keys.into() }
+    }
+
+    /// The key new cookies are signed or encrypted with
+    pub fn current(&self) -> &Key {
+        &self.keys[0]
+    }
+
+    /// Every accepted key, starting with the current one
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+}
+
+impl std::fmt::Debug for CookieKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CookieKeys").field("keys", &self.keys.len()).finish()
+    }
+}
+
+macro_rules! secure_jar {
+    ($(#[$meta: meta])* $name: ident, $jar: ident, $jar_mut: ident, $read: ident) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        pub struct $name {
+            jar: CookieJar,
+            keys: CookieKeys,
+        }
+
+        impl $name {
+            /// Get a cookie from the request, `None` when it's missing or wasn't made with one of
+            /// the keys
+            pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+                let cookie = self.jar.get(name)?;
+                let jar = cookie::CookieJar::new();
+                self.keys.keys().iter().find_map(|key| jar.$jar(key).$read(cookie.clone()))
+            }
+
+            /// Add a cookie to the response, made with the current key
+            pub fn add<C: Into<Cookie<'static>>>(&self, cookie: C) {
+                self.jar.with(|jar| jar.$jar_mut(self.keys.current()).add(cookie));
+            }
+
+            /// Remove a cookie from the client
+            pub fn remove<C: Into<Cookie<'static>>>(&self, cookie: C) {
+                self.jar.remove(cookie);
+            }
+
+            /// The jar with the cookies as they are sent
+            pub fn jar(&self) -> &CookieJar {
+                &self.jar
+            }
+        }
+
+        impl FromParts for $name {
+            type Rejection = MissingState;
+
+            async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Self::Rejection> {
+                let keys = match shared::<CookieKeys>(&parts.extensions) {
+                    Some(keys) => keys.clone(),
+                    None => return Err(MissingState(type_name::<CookieKeys>())),
+                };
+                let Ok(jar) = CookieJar::from_parts(parts, jar).await;
+                Ok(Self { jar, keys })
+            }
+        }
+    };
+}
+
+secure_jar!(
+    /// Cookies that are signed so they can't be changed by the client, the value is still
+    /// readable.
+    ///
+    /// Needs [`CookieKeys`] as a state or sub state of the router, otherwise the request is
+    /// rejected with [`MissingState`].
+    SignedCookies, signed, signed_mut, verify
+);
+
+secure_jar!(
+    /// Cookies that are encrypted so the client can't read or change them.
+    ///
+    /// Needs [`CookieKeys`] as a state or sub state of the router, otherwise the request is
+    /// rejected with [`MissingState`].
+    PrivateCookies, private, private_mut, decrypt
+);
+
+#[cfg(test)]
+mod tests {
+    use hyper::header;
+
+    use super::*;
+    use crate::{extract::Shared, Request};
+
+    fn parts(keys: Option<CookieKeys>, cookie: Option<&str>) -> Parts {
+        let mut request = Request::builder().uri("/");
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        if let Some(keys) = keys {
+            request = request.extension(Shared(keys));
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    /// The `name=value` pairs a client would send back
+    fn set_cookies(response: &Response) -> Vec<String> {
+        response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().split(';').next().unwrap().to_string())
+            .collect()
+    }
+
+    async fn signed(keys: &CookieKeys, cookie: &str) -> SignedCookies {
+        let Ok(jar) = SignedCookies::from_parts(&parts(Some(keys.clone()), Some(cookie)), CookieJar::default()).await else {
+            panic!("keys are missing");
+        };
+        jar
+    }
+
+    async fn private(keys: &CookieKeys, cookie: &str) -> PrivateCookies {
+        let Ok(jar) = PrivateCookies::from_parts(&parts(Some(keys.clone()), Some(cookie)), CookieJar::default()).await else {
+            panic!("keys are missing");
+        };
+        jar
+    }
+
+    /// Add a cookie to a new jar and return what the client would send back
+    fn issue(add: impl FnOnce(CookieJar)) -> String {
+        let jar = CookieJar::default();
+        add(jar.clone());
+        let Ok(response) = jar.into_response_parts(Response::default());
+        set_cookies(&response).join("; ")
+    }
+
+    #[tokio::test]
+    async fn reads_request_cookies() {
+        let Ok(jar) = CookieJar::from_parts(&parts(None, Some("a=1; b=two")), CookieJar::default()).await;
+        assert_eq!(jar.get("a").unwrap().value(), "1");
+        assert_eq!(jar.get("b").unwrap().value(), "two");
+        assert!(jar.get("c").is_none());
+
+        // Cookies that weren't changed aren't sent back
+        let Ok(response) = jar.into_response_parts(Response::default());
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+    }
+
+    #[test]
+    fn one_header_per_cookie_with_attributes() {
+        let jar = CookieJar::default();
+        jar.add(
+            Cookie::build(("a", "1"))
+                .path("/admin")
+                .max_age(cookie::time::Duration::hours(1))
+                .same_site(cookie::SameSite::Strict)
+                .http_only(true),
+        );
+        jar.add(Cookie::new("b", "2"));
+
+        let Ok(response) = jar.into_response_parts(Response::default());
+        let values = response.headers().get_all(header::SET_COOKIE).iter().collect::<Vec<_>>();
+        assert_eq!(values.len(), 2);
+        let a = values.iter().find(|v| v.to_str().unwrap().starts_with("a=1")).unwrap().to_str().unwrap();
+        assert!(a.contains("Path=/admin"));
+        assert!(a.contains("Max-Age=3600"));
+        assert!(a.contains("SameSite=Strict"));
+        assert!(a.contains("HttpOnly"));
+    }
+
+    #[test]
+    fn changes_are_sent_once() {
+        let jar = CookieJar::default();
+        jar.add(Cookie::new("a", "1"));
+        let Ok(response) = jar.clone().into_response_parts(Response::default());
+        assert_eq!(set_cookies(&response), ["a=1"]);
+
+        let Ok(response) = jar.into_response_parts(Response::default());
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+    }
+
+    #[test]
+    fn removal_keeps_path() {
+        let Ok(response) = Cookie::build(("a", "1")).path("/x").build().into_response_parts(Response::default());
+        assert_eq!(set_cookies(&response), ["a=1"]);
+
+        let jar = CookieJar::default();
+        jar.with(|jar| jar.add_original(Cookie::new("a", "1")));
+        jar.remove(Cookie::build("a").path("/x"));
+        let Ok(response) = jar.into_response_parts(Response::default());
+        let value = response.headers().get(header::SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(value.contains("Path=/x"));
+        assert!(value.contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn jar_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CookieJar>();
+        assert_send_sync::<SignedCookies>();
+        assert_send_sync::<PrivateCookies>();
+    }
+
+    #[tokio::test]
+    async fn signed_round_trip() {
+        let keys = CookieKeys::new(Key::generate());
+        let sent = issue(|jar| {
+            jar.with(|jar| jar.signed_mut(keys.current()).add(Cookie::new("user", "ann")));
+        });
+        // The value is readable but signed
+        assert!(sent.starts_with("user="));
+        assert!(sent.ends_with("ann"));
+        assert_eq!(signed(&keys, &sent).await.get("user").unwrap().value(), "ann");
+    }
+
+    #[tokio::test]
+    async fn signed_rejects_tampering() {
+        let keys = CookieKeys::new(Key::generate());
+        let sent = issue(|jar| {
+            jar.with(|jar| jar.signed_mut(keys.current()).add(Cookie::new("user", "ann")));
+        });
+        let tampered = format!("{}bob", sent.strip_suffix("ann").unwrap());
+        assert!(signed(&keys, &tampered).await.get("user").is_none());
+        assert!(signed(&keys, "user=ann").await.get("user").is_none());
+
+        let other = CookieKeys::new(Key::generate());
+        assert!(signed(&other, &sent).await.get("user").is_none());
+    }
+
+    #[tokio::test]
+    async fn private_round_trip() {
+        let keys = CookieKeys::new(Key::generate());
+        let sent = issue(|jar| {
+            jar.with(|jar| jar.private_mut(keys.current()).add(Cookie::new("token", "secret")));
+        });
+        // The value can't be read by the client
+        assert!(!sent.contains("secret"));
+        assert_eq!(private(&keys, &sent).await.get("token").unwrap().value(), "secret");
+
+        let mut tampered = sent.clone();
+        tampered.pop();
+        tampered.push(if sent.ends_with('A') { 'B' } else { 'A' });
+        assert!(private(&keys, &tampered).await.get("token").is_none());
+        assert!(private(&CookieKeys::new(Key::generate()), &sent).await.get("token").is_none());
+    }
+
+    #[tokio::test]
+    async fn previous_keys_are_accepted() {
+        let old = Key::generate();
+        let before = CookieKeys::new(old.clone());
+        let signed_sent = issue(|jar| jar.with(|jar| jar.signed_mut(before.current()).add(Cookie::new("a", "1"))));
+        let private_sent = issue(|jar| jar.with(|jar| jar.private_mut(before.current()).add(Cookie::new("b", "2"))));
+
+        let rotated = CookieKeys::new(Key::generate()).with_previous(old);
+        assert_eq!(signed(&rotated, &signed_sent).await.get("a").unwrap().value(), "1");
+        assert_eq!(private(&rotated, &private_sent).await.get("b").unwrap().value(), "2");
+
+        // New cookies use the current key, so they aren't accepted with only the old one
+        let jar = signed(&rotated, "").await;
+        jar.add(Cookie::new("c", "3"));
+        let Ok(response) = jar.jar().clone().into_response_parts(Response::default());
+        let sent = set_cookies(&response).join("; ");
+        assert!(signed(&before, &sent).await.get("c").is_none());
+        assert_eq!(signed(&rotated, &sent).await.get("c").unwrap().value(), "3");
+    }
+
+    #[tokio::test]
+    async fn missing_keys_are_rejected() {
+        assert!(SignedCookies::from_parts(&parts(None, None), CookieJar::default()).await.is_err());
+        assert!(PrivateCookies::from_parts(&parts(None, None), CookieJar::default()).await.is_err());
+    }
+}
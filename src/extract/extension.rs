@@ -0,0 +1,137 @@
+use std::{any::type_name, convert::Infallible, fmt::Display, ops::{Deref, DerefMut}, task::{Context, Poll}};
+
+use hyper::http::request::Parts;
+use tower::{Layer, Service};
+
+use crate::{Request, Response};
+
+use super::{rejection::reject_setup, request::{FromParts, OptionalFromParts}, CookieJar, IntoResponse, IntoResponseParts};
+
+#[derive(Debug)]
+pub struct MissingExtension(&'static str);
+impl Display for MissingExtension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Missing request extension `{}`; make sure a layer around this route inserts it, for example with `.layer(Extension(value))`",
+            self.0
+        )
+    }
+}
+impl std::error::Error for MissingExtension {}
+
+impl IntoResponse for MissingExtension {
+    fn into_response(self) -> Response {
+        reject_setup(self)
+    }
+}
+
+/// A value from the request extensions, usually added by a layer like the authenticated user or
+/// a request id.
+///
+/// It is also a layer that adds a clone of the value to every request, and a response part that
+/// adds the value to the response extensions.
+///
+/// ```ignore
+/// PathRouter::default()
+///     .route("/", get(|Extension(pool): Extension<Pool>| async move { ... }))
+///     .route_layer(Extension(pool))
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Extension<T>(pub T);
+
+impl<T> Deref for Extension<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Extension<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> FromParts for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingExtension;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<T>() {
+            Some(value) => Ok(Extension(value.clone())),
+            None => Err(MissingExtension(type_name::<T>())),
+        }
+    }
+}
+
+impl<T> OptionalFromParts for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<T>().cloned().map(Extension))
+    }
+}
+
+impl<T> IntoResponseParts for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: Response) -> Result<Response, Self::Error> {
+        res.extensions_mut().insert(self.0);
+        Ok(res)
+    }
+}
+
+impl<T> IntoResponse for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn into_response(self) -> Response {
+        let mut response = ().into_response();
+        response.extensions_mut().insert(self.0);
+        response
+    }
+}
+
+impl<S, T: Clone> Layer<S> for Extension<T> {
+    type Service = AddExtension<S, T>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        AddExtension { service, value: self.0.clone() }
+    }
+}
+
+/// Service that adds a clone of a value to the extensions of every request
+#[derive(Debug, Clone)]
+pub struct AddExtension<S, T> {
+    service: S,
+    value: T,
+}
+
+impl<S, T> Service<Request> for AddExtension<S, T>
+where
+    S: Service<Request>,
+    T: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        req.extensions_mut().insert(self.value.clone());
+        self.service.call(req)
+    }
+}
@@ -0,0 +1,253 @@
+use std::convert::Infallible;
+
+use hyper::http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+use crate::Response;
+
+use super::{request::FromParts, Cookie, CookieJar, IntoResponseParts, MissingState, shared, SignedCookies};
+
+const FLASH_COOKIE: &str = "flash";
+
+/// Browsers drop cookies over 4 KB, this leaves room for the name and the signature
+const MAX_FLASH_SIZE: usize = 3 * 1024;
+
+/// Options for the [`Flash`] cookie, added to the router as a state. Without it the cookie is
+/// `Secure`, like the session cookie of [`SessionLayer`](crate::session::SessionLayer).
+///
+/// ```ignore
+/// PathRouter::default().with_state(FlashConfig::default().with_secure(false))
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlashConfig {
+    secure: bool,
+}
+
+impl Default for FlashConfig {
+    fn default() -> Self {
+        Self { secure: true }
+    }
+}
+
+impl FlashConfig {
+    /// Only send the cookie over https, turn it off for local development over http
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLevel {
+    Debug,
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A message from [`Flash`], serialized as `{"level": "success", "text": "..."}` for templates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashMessage {
+    level: FlashLevel,
+    text: String,
+}
+
+impl FlashMessage {
+    pub fn new<S: Into<String>>(level: FlashLevel, text: S) -> Self {
+        Self { level, text: text.into() }
+    }
+
+    pub fn level(&self) -> FlashLevel {
+        self.level
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// One time messages that survive a redirect, kept in a signed cookie.
+///
+/// The messages of the previous request are consumed when it's extracted. New messages are sent
+/// when it's returned as a response part. Needs [`CookieKeys`](super::CookieKeys) in the router
+/// state, and [`TemplateRouter`](crate::server::TemplateRouter) adds the messages to the render
+/// data as `flash`.
+///
+/// The cookie is limited to about 3 KB, messages that don't fit are dropped with a warning.
+///
+/// ```ignore
+/// async fn save(flash: Flash, UrlEncoded(post): UrlEncoded<Post>) -> impl IntoResponse {
+///     (flash.success("Your post was saved"), Redirect::see_other("/posts"))
+/// }
+///
+/// async fn posts(flash: Flash) -> Html<String> {
+///     for message in flash.messages() { ... }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Flash {
+    cookies: SignedCookies,
+    config: FlashConfig,
+    messages: Vec<FlashMessage>,
+    outgoing: Vec<FlashMessage>,
+}
+
+impl Flash {
+    /// Messages from the previous request
+    pub fn messages(&self) -> &[FlashMessage] {
+        &self.messages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Add a message for the next request
+    pub fn with<S: Into<String>>(mut self, level: FlashLevel, text: S) -> Self {
+        self.outgoing.push(FlashMessage::new(level, text));
+        self
+    }
+
+    pub fn debug<S: Into<String>>(self, text: S) -> Self {
+        self.with(FlashLevel::Debug, text)
+    }
+
+    pub fn info<S: Into<String>>(self, text: S) -> Self {
+        self.with(FlashLevel::Info, text)
+    }
+
+    pub fn success<S: Into<String>>(self, text: S) -> Self {
+        self.with(FlashLevel::Success, text)
+    }
+
+    pub fn warning<S: Into<String>>(self, text: S) -> Self {
+        self.with(FlashLevel::Warning, text)
+    }
+
+    pub fn error<S: Into<String>>(self, text: S) -> Self {
+        self.with(FlashLevel::Error, text)
+    }
+
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        Cookie::build((FLASH_COOKIE, value))
+            .path("/")
+            .http_only(true)
+            .secure(self.config.secure)
+            .same_site(cookie::SameSite::Lax)
+            .build()
+    }
+
+    /// The outgoing messages as json, without the last ones that don't fit in the cookie
+    fn serialize(mut messages: Vec<FlashMessage>) -> Option<String> {
+        while !messages.is_empty() {
+            let value = match serde_json::to_string(&messages) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::error!("Failed to serialize flash messages: {}", e);
+                    return None;
+                },
+            };
+            // The value is percent encoded in the cookie
+            if Cookie::new(FLASH_COOKIE, value.as_str()).encoded().to_string().len() <= MAX_FLASH_SIZE {
+                return Some(value);
+            }
+            let dropped = messages.pop();
+            log::warn!("Flash message dropped since the cookie is too large: {:?}", dropped.map(|m| m.text));
+        }
+        None
+    }
+}
+
+impl FromParts for Flash {
+    type Rejection = MissingState;
+
+    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        let cookies = SignedCookies::from_parts(parts, jar).await?;
+        let config = shared::<FlashConfig>(&parts.extensions).cloned().unwrap_or_default();
+        let mut flash = Self { cookies, config, messages: Vec::new(), outgoing: Vec::new() };
+        // Messages that were changed or can't be read are dropped as well
+        if let Some(cookie) = flash.cookies.get(FLASH_COOKIE) {
+            flash.cookies.remove(flash.cookie(String::new()));
+            flash.messages = serde_json::from_str(cookie.value()).unwrap_or_default();
+        }
+        Ok(flash)
+    }
+}
+
+/// The messages are added to the cookie jar, where the changes are kept by name, so they replace
+/// the removal of the consumed messages
+impl IntoResponseParts for Flash {
+    type Error = Infallible;
+
+    fn into_response_parts(mut self, res: Response) -> Result<Response, Self::Error> {
+        if let Some(value) = Self::serialize(std::mem::take(&mut self.outgoing)) {
+            self.cookies.add(self.cookie(value));
+        }
+        self.cookies.jar().clone().into_response_parts(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header;
+
+    use super::*;
+    use crate::{extract::{CookieKeys, Key, Shared}, Request};
+
+    async fn flash(keys: &CookieKeys, config: Option<FlashConfig>, cookie: &str) -> Flash {
+        let mut request = Request::builder().header(header::COOKIE, cookie).extension(Shared(keys.clone()));
+        if let Some(config) = config {
+            request = request.extension(Shared(config));
+        }
+        let (parts, _) = request.body(()).unwrap().into_parts();
+        let Ok(flash) = Flash::from_parts(&parts, CookieJar::default()).await else {
+            panic!("keys are missing");
+        };
+        flash
+    }
+
+    fn set_cookie(response: &Response) -> &str {
+        response.headers().get(header::SET_COOKIE).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn messages_survive_one_request() {
+        let keys = CookieKeys::new(Key::generate());
+        let Ok(response) = flash(&keys, None, "").await.success("Saved").into_response_parts(Response::default());
+        let sent = set_cookie(&response);
+        assert!(sent.contains("Secure"));
+
+        let cookie = sent.split(';').next().unwrap();
+        let next = flash(&keys, None, cookie).await;
+        assert_eq!(next.messages(), [FlashMessage::new(FlashLevel::Success, "Saved")]);
+        // Consumed, so the cookie is removed
+        let Ok(response) = next.into_response_parts(Response::default());
+        assert!(set_cookie(&response).contains("Max-Age=0"));
+    }
+
+    #[tokio::test]
+    async fn secure_can_be_turned_off() {
+        let keys = CookieKeys::new(Key::generate());
+        let config = FlashConfig::default().with_secure(false);
+        let Ok(response) = flash(&keys, Some(config), "").await.info("Hi").into_response_parts(Response::default());
+        assert!(!set_cookie(&response).contains("Secure"));
+    }
+
+    #[tokio::test]
+    async fn large_messages_are_dropped() {
+        let keys = CookieKeys::new(Key::generate());
+        let mut outgoing = flash(&keys, None, "").await.info("first");
+        for _ in 0..10 {
+            outgoing = outgoing.info("x".repeat(1024));
+        }
+        let Ok(response) = outgoing.into_response_parts(Response::default());
+        let sent = set_cookie(&response);
+        assert!(sent.len() < 4096);
+
+        let next = flash(&keys, None, sent.split(';').next().unwrap()).await;
+        assert_eq!(next.messages()[0].text(), "first");
+        assert!(next.messages().len() < 11);
+    }
+}
@@ -15,7 +15,7 @@ pub use from_form::{FromForm, FromFormField, FromFormCollect};
 #[allow(unused_imports)]
 pub use multer::{Constraints, SizeLimit, Field};
 
-use super::request::FromRequest;
+use super::{rejection::MultipartRejection, request::{FromRequest, OptionalFromRequest}};
 
 
 pub struct Form<T = multer::Multipart<'static>>(pub T);
@@ -37,52 +37,78 @@ impl<T: Clone> Clone for Form<T> {
     }
 }
 
+/// The `multipart/form-data` boundary, `None` when the request has no `Content-Type`
+fn boundary(request: &crate::Request) -> Result<Option<String>, MultipartRejection> {
+    let content_type = match request.headers().get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type,
+        None => return Ok(None),
+    };
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|ct| multer::parse_boundary(ct).ok())
+        .map(Some)
+        .ok_or(MultipartRejection::UnsupportedMediaType)
+}
+
+async fn parse<T: FromForm + Send>(request: crate::Request, boundary: String) -> Result<Form<T>, MultipartRejection> {
+    // Convert the body into a stream of data frames.
+    let body_stream = request.into_body().into_data_stream();
+    let mut multipart = multer::Multipart::with_constraints(body_stream, boundary, T::settings());
+
+    let mut form: T::Form = T::init();
+    while let Some(field) = multipart.next_field().await? {
+        form = T::push_field(form, field).await;
+    }
+
+    // Fields are read while the form is built, so reading errors end up with the form errors
+    T::finilize(form).map(Form).map_err(|error| match error.downcast::<multer::Error>() {
+        Ok(error) => MultipartRejection::from(*error),
+        Err(error) => MultipartRejection::Invalid(error),
+    })
+}
+
+fn stream(request: crate::Request, boundary: String) -> Form {
+    let body_stream = request.into_body().into_data_stream();
+    Form(multer::Multipart::with_constraints(body_stream, boundary, Constraints::default()))
+}
+
 impl<T: FromForm + Send> FromRequest for Form<T> {
-    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, crate::Error> {
-        // Extract the `multipart/form-data` boundary from the headers.
-        let boundary = request
-            .headers()
-            .get(header::CONTENT_TYPE)
-            .and_then(|ct| ct.to_str().ok())
-            .and_then(|ct| multer::parse_boundary(ct).ok());
-
-        if boundary.is_none() {
-            return Err("BAD REQUEST: Invalid content type".into());
-        }
+    type Rejection = MultipartRejection;
 
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, Self::Rejection> {
+        let boundary = boundary(&request)?.ok_or(MultipartRejection::UnsupportedMediaType)?;
+        parse(request, boundary).await
+    }
+}
 
-        // Convert the body into a stream of data frames.
-        let body_stream = request.into_body().into_data_stream();
-        let mut multipart = multer::Multipart::with_constraints(body_stream, boundary.unwrap(), T::settings());
+/// `None` when the request has no `Content-Type`
+impl<T: FromForm + Send> OptionalFromRequest for Form<T> {
+    type Rejection = MultipartRejection;
 
-        let mut form: T::Form = T::init();
-        while let Some(field) = multipart.next_field().await? {
-            form = T::push_field(form, field).await;
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        match boundary(&request)? {
+            Some(boundary) => parse(request, boundary).await.map(Some),
+            None => Ok(None),
         }
-
-        Ok(Form(T::finilize(form)?))
     }
 }
 
 impl FromRequest for Form {
-    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, crate::Error> {
-        // Extract the `multipart/form-data` boundary from the headers.
-        let boundary = request
-            .headers()
-            .get(header::CONTENT_TYPE)
-            .and_then(|ct| ct.to_str().ok())
-            .and_then(|ct| multer::parse_boundary(ct).ok());
-
-        if boundary.is_none() {
-            return Err("BAD REQUEST: Invalid content type".into());
-        }
+    type Rejection = MultipartRejection;
 
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, Self::Rejection> {
+        let boundary = boundary(&request)?.ok_or(MultipartRejection::UnsupportedMediaType)?;
+        Ok(stream(request, boundary))
+    }
+}
 
-        // Convert the body into a stream of data frames.
-        let body_stream = request.into_body().into_data_stream();
-        let multipart = multer::Multipart::with_constraints(body_stream, boundary.unwrap(), Constraints::default());
+/// `None` when the request has no `Content-Type`
+impl OptionalFromRequest for Form {
+    type Rejection = MultipartRejection;
 
-        Ok(Form(multipart))
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(boundary(&request)?.map(|boundary| stream(request, boundary)))
     }
 }
 
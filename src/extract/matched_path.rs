@@ -0,0 +1,67 @@
+use std::{convert::Infallible, fmt::Display, ops::Deref, sync::Arc};
+
+use hyper::{http::request::Parts, StatusCode};
+
+use crate::Response;
+
+use super::{rejection::reject, request::{FromParts, OptionalFromParts}, CookieJar, IntoResponse};
+
+#[derive(Debug)]
+pub struct MissingMatchedPath;
+impl Display for MissingMatchedPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No matched path; the handler was not called from a PathRouter route")
+    }
+}
+impl std::error::Error for MissingMatchedPath {}
+
+impl IntoResponse for MissingMatchedPath {
+    fn into_response(self) -> Response {
+        reject(StatusCode::INTERNAL_SERVER_ERROR, self)
+    }
+}
+
+/// The route pattern that matched the current request, for example `/users/:id`.
+///
+/// When routers are nested this is the pattern of the inner most route that matched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatchedPath(pub(crate) Arc<str>);
+
+impl MatchedPath {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for MatchedPath {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl Display for MatchedPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromParts for MatchedPath {
+    type Rejection = MissingMatchedPath;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        parts.extensions
+            .get::<MatchedPath>()
+            .cloned()
+            .ok_or(MissingMatchedPath)
+    }
+}
+
+impl OptionalFromParts for MatchedPath {
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<MatchedPath>().cloned())
+    }
+}
@@ -1,5 +1,4 @@
-use http_body_util::BodyExt;
-pub use hyper::{Method, StatusCode, body::{Incoming, Bytes}};
+pub use hyper::{Method, StatusCode, Uri, Version, HeaderMap, body::{Incoming, Bytes}, http::request::Parts};
 pub use tokio::fs::File;
 
 mod request;
@@ -9,19 +8,52 @@ mod capture;
 mod redirect;
 mod wrapper;
 mod form_data;
+mod state;
+mod matched_path;
+mod caught;
+mod typed_path;
+mod rejection;
+mod problem;
+mod typed_header;
+mod extension;
+mod flash;
 
-pub use cookies::{CookieJar, Cookie};
+pub use cookies::{CookieJar, Cookie, CookieKeys, Key, SignedCookies, PrivateCookies};
 pub use capture::{Capture, UriParams};
 pub use redirect::Redirect;
-pub use response::IntoResponse;
-pub use request::{FromRequest, FromParts};
-pub use wrapper::{Html, Json, Query, UrlEncoded};
+pub use matched_path::MatchedPath;
+pub use caught::Caught;
+pub use typed_path::{TypedPath, __encode};
+pub use typed_header::{headers, TypedHeader, TypedHeaderRejection};
+pub use state::{State, FromRef, MissingState};
+pub use extension::{Extension, AddExtension, MissingExtension};
+pub use flash::{Flash, FlashConfig, FlashLevel, FlashMessage};
+pub(crate) use state::{shared, RouterState, Shared};
+pub use response::{IntoResponse, IntoResponseParts};
+pub(crate) use response::ResponseError;
+pub use request::{FromRequest, FromParts, OptionalFromRequest, OptionalFromParts, DEFAULT_BODY_LIMIT};
+pub(crate) use request::BodyLimit;
+pub use rejection::{RejectionBody, BodyRejection, JsonRejection, QueryRejection, UrlEncodedRejection, PathRejection, MultipartRejection};
+pub(crate) use rejection::{reject_setup, render as render_rejection, PublicMessage};
+pub use wrapper::{Html, Json, Lenient, Query, UrlEncoded};
+pub use problem::{Problem, APPLICATION_PROBLEM_JSON};
 pub use form_data::{Form as Multipart, FromFormField, FromForm, FromFormCollect, SizeLimit, Field as FormField, TempFile};
-pub use wayfinder_macros::Form;
+pub use wayfinder_macros::{Form, TypedPath};
 
 impl FromRequest for Bytes {
-    async fn from_request(request: crate::Request, _: CookieJar) -> Result<Self, crate::Error> {
-        Ok(request.into_body().collect().await?.to_bytes())
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: crate::Request, _: CookieJar) -> Result<Self, Self::Rejection> {
+        request::collect(request).await
+    }
+}
+
+impl OptionalFromRequest for Bytes {
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: crate::Request, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        let body = request::collect(request).await?;
+        Ok((!body.is_empty()).then_some(body))
     }
 }
 
@@ -0,0 +1,169 @@
+use hyper::{header, StatusCode};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::{HttpError, Response};
+
+use super::{IntoResponse, Json};
+
+pub const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// An RFC 9457 problem details document, sent as `application/problem+json`.
+///
+/// ```ignore
+/// Problem::new(StatusCode::FORBIDDEN)
+///     .with_type("https://example.com/probs/out-of-credit")
+///     .with_detail("Your current balance is 30, but that costs 50.")
+///     .with("balance", 30)
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(serialize_with = "serialize_status")]
+    status: StatusCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+/// Members that are serialized from the standard fields
+const RESERVED_MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+fn serialize_status<S: Serializer>(status: &StatusCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(status.as_u16())
+}
+
+impl Problem {
+    /// A problem with the title of the status. Without a type the problem is `about:blank`, which
+    /// means it has no meaning beyond the status.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            kind: None,
+            title: status.canonical_reason().map(str::to_string),
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// URI that identifies the problem type
+    pub fn with_type<S: Into<String>>(mut self, kind: S) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Short summary of the problem type, it shouldn't change between occurrences
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Explanation specific to this occurrence of the problem
+    pub fn with_detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// URI that identifies this occurrence of the problem
+    pub fn with_instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member. Values that can't be serialized are skipped, as are members named
+    /// like a standard field since they would be sent twice.
+    pub fn with<V: Serialize>(mut self, key: &str, value: V) -> Self {
+        if RESERVED_MEMBERS.contains(&key) {
+            log::warn!("Problem member `{}` is reserved, use it's builder method instead", key);
+            return self;
+        }
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.extensions.insert(key.to_string(), value);
+            },
+            Err(e) => log::error!("Failed to serialize problem member `{}`: {}", key, e),
+        }
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    pub fn extensions(&self) -> &Map<String, Value> {
+        &self.extensions
+    }
+}
+
+impl From<StatusCode> for Problem {
+    fn from(status: StatusCode) -> Self {
+        Self::new(status)
+    }
+}
+
+/// Only the public message of the error is used as the detail
+impl From<HttpError> for Problem {
+    fn from(error: HttpError) -> Self {
+        if error.status().is_server_error() {
+            log::error!("{}", error);
+        }
+        let problem = Self::new(error.status());
+        match error.message() {
+            Some(message) => problem.with_detail(message),
+            None => problem,
+        }
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = self.status;
+        let mut response = Json(self).into_response();
+        if response.status().is_success() {
+            *response.status_mut() = status;
+            response.headers_mut().insert(header::CONTENT_TYPE, APPLICATION_PROBLEM_JSON.parse().unwrap());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_members_are_ignored() {
+        let problem = Problem::new(StatusCode::FORBIDDEN)
+            .with("status", 200)
+            .with("title", "Other")
+            .with("balance", 30);
+        let body = serde_json::to_value(&problem).unwrap();
+        assert_eq!(body["status"], 403);
+        assert_eq!(body["title"], "Forbidden");
+        assert_eq!(body["balance"], 30);
+        assert_eq!(problem.extensions().len(), 1);
+    }
+}
@@ -105,7 +105,7 @@ impl IntoResponse for Redirect {
         if let Some(location) = self.location {
             response = response.header(header::LOCATION, location);
         }
-        response.body(self.choices.unwrap_or(Body::empty()))
+        response.body(self.choices.unwrap_or_default())
         .unwrap()
     }
 }
@@ -0,0 +1,342 @@
+use std::{fmt::Display, string::FromUtf8Error, sync::Arc};
+
+use hyper::{body::Body as _, header, StatusCode};
+
+use crate::{Body, Response};
+
+use super::{problem::Problem, IntoResponse, ResponseError};
+
+/// How the body of an extractor rejection or [`HttpError`](crate::HttpError) is rendered.
+///
+/// Rejections respond with an empty body by default so they can be handled by catchers. Only
+/// client errors are explained, server errors don't expose their message unless it is about how
+/// the router is set up, like a missing [`Extension`](super::Extension).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionBody {
+    #[default]
+    Empty,
+    /// A `text/plain` explanation
+    Text,
+    /// An `application/json` object with the `status`, `error` and `message` of the rejection
+    Json,
+    /// An RFC 9457 [`Problem`](super::Problem) document with the explanation as the `detail`
+    Problem,
+}
+
+/// Explanation of a client error that is safe to send back to the client
+#[derive(Clone)]
+pub(crate) struct PublicMessage(pub String);
+
+/// Build the response for a rejected request. The error is kept in the response extensions so
+/// catchers can inspect it.
+pub(crate) fn reject<E>(status: StatusCode, error: E) -> Response
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let message = status.is_client_error().then(|| PublicMessage(error.to_string()));
+    if status.is_server_error() {
+        log::error!("{}", error);
+    } else {
+        log::debug!("Request rejected with {}: {}", status, error);
+    }
+
+    let mut response = Response::builder()
+        .status(status)
+        .extension(ResponseError(Arc::new(Box::new(error))))
+        .body(Body::empty())
+        .unwrap();
+    if let Some(message) = message {
+        response.extensions_mut().insert(message);
+    }
+    response
+}
+
+/// Build the response for a server error that is only about how the router is set up, like a
+/// missing layer. Unlike other server errors it's explanation can be rendered with
+/// [`PathRouter::rejection_body`](crate::server::PathRouter::rejection_body).
+pub(crate) fn reject_setup<E>(error: E) -> Response
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let message = PublicMessage(error.to_string());
+    let mut response = reject(StatusCode::INTERNAL_SERVER_ERROR, error);
+    response.extensions_mut().insert(message);
+    response
+}
+
+/// Fill the empty body of a rejection with it's explanation
+pub(crate) fn render(format: RejectionBody, mut response: Response) -> Response {
+    if format == RejectionBody::Empty || response.body().size_hint().exact() != Some(0) {
+        return response;
+    }
+    let message = response.extensions_mut().remove::<PublicMessage>().map(|PublicMessage(message)| message);
+    let status = response.status();
+
+    // Problem documents are used for every error, not only the ones with an explanation
+    if format == RejectionBody::Problem {
+        if message.is_none() && response.extensions().get::<ResponseError>().is_none() {
+            return response;
+        }
+        let problem = match message {
+            Some(message) => Problem::new(status).with_detail(message),
+            None => Problem::new(status),
+        };
+        let (mut parts, _) = response.into_parts();
+        let (rendered, body) = problem.into_response().into_parts();
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.extend(rendered.headers);
+        return Response::from_parts(parts, body);
+    }
+
+    let message = match message {
+        Some(message) => message,
+        None => return response,
+    };
+    let (content_type, body) = match format {
+        RejectionBody::Json => (
+            mime::APPLICATION_JSON.as_ref(),
+            serde_json::json!({
+                "status": status.as_u16(),
+                "error": status.canonical_reason().unwrap_or_default(),
+                "message": message,
+            }).to_string(),
+        ),
+        _ => (mime::TEXT_PLAIN_UTF_8.as_ref(), message),
+    };
+
+    response.headers_mut().remove(header::CONTENT_LENGTH);
+    response.headers_mut().insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+    *response.body_mut() = Body::from(body);
+    response
+}
+
+macro_rules! impl_rejection {
+    ($($ty: ident),* $(,)?) => {
+        $(
+            impl std::error::Error for $ty {}
+
+            impl IntoResponse for $ty {
+                fn into_response(self) -> Response {
+                    reject(self.status(), self)
+                }
+            }
+        )*
+    };
+}
+
+impl_rejection!(BodyRejection, JsonRejection, QueryRejection, UrlEncodedRejection, PathRejection, MultipartRejection);
+
+/// Rejection for extractors that collect the request body, like `String` and `Bytes`
+#[derive(Debug)]
+pub enum BodyRejection {
+    /// The body couldn't be read
+    Read(crate::Error),
+    /// The body isn't valid utf-8
+    InvalidUtf8(FromUtf8Error),
+    /// The body is larger than the limit, in bytes
+    TooLarge(usize),
+}
+
+impl BodyRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl Display for BodyRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(error) => write!(f, "Failed to read the request body: {}", error),
+            Self::InvalidUtf8(error) => write!(f, "Request body is not valid utf-8: {}", error),
+            Self::TooLarge(limit) => write!(f, "Request body is larger than the limit of {} bytes", limit),
+        }
+    }
+}
+
+/// Rejection for the [`Json`](super::Json) extractor
+#[derive(Debug)]
+pub enum JsonRejection {
+    /// The request isn't `application/json` or a `+json` media type
+    UnsupportedMediaType,
+    Body(BodyRejection),
+    /// The body isn't valid json
+    Syntax(serde_json::Error),
+    /// The body is valid json but doesn't match the expected type. The path points to the value
+    /// that didn't match, for example `items[3].price`, and is `.` for the whole document.
+    Data {
+        path: String,
+        error: serde_json::Error,
+    },
+}
+
+impl JsonRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Body(rejection) => rejection.status(),
+            Self::Syntax(_) => StatusCode::BAD_REQUEST,
+            Self::Data { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    /// Path to the value that didn't match the expected type
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Data { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Line and column in the body where parsing failed
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Syntax(error) | Self::Data { error, .. } => Some((error.line(), error.column())),
+            _ => None,
+        }
+    }
+}
+
+impl Display for JsonRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedMediaType => write!(f, "Expected a request with `Content-Type: application/json`"),
+            Self::Body(rejection) => rejection.fmt(f),
+            Self::Syntax(error) => write!(f, "Request body is not valid json: {}", error),
+            Self::Data { path, error } if path == "." => {
+                write!(f, "Request body doesn't match the expected json: {}", error)
+            },
+            Self::Data { path, error } => write!(f, "Invalid value for `{}`: {}", path, error),
+        }
+    }
+}
+
+/// Rejection for the [`Query`](super::Query) extractor
+#[derive(Debug)]
+pub enum QueryRejection {
+    /// The query string doesn't match the expected type
+    Deserialize(serde_urlencoded::de::Error),
+}
+
+impl QueryRejection {
+    pub fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl Display for QueryRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Deserialize(error) => write!(f, "Invalid query string: {}", error),
+        }
+    }
+}
+
+/// Rejection for the [`UrlEncoded`](super::UrlEncoded) extractor
+#[derive(Debug)]
+pub enum UrlEncodedRejection {
+    /// The request isn't `application/x-www-form-urlencoded`
+    UnsupportedMediaType,
+    Body(BodyRejection),
+    /// The body doesn't match the expected type
+    Deserialize(serde_urlencoded::de::Error),
+}
+
+impl UrlEncodedRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Body(rejection) => rejection.status(),
+            Self::Deserialize(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl Display for UrlEncodedRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedMediaType => write!(f, "Expected a request with `Content-Type: application/x-www-form-urlencoded`"),
+            Self::Body(rejection) => rejection.fmt(f),
+            Self::Deserialize(error) => write!(f, "Invalid form body: {}", error),
+        }
+    }
+}
+
+/// Rejection for the [`Capture`](super::Capture) extractor
+#[derive(Debug)]
+pub enum PathRejection {
+    /// The handler wasn't called from a route with captures
+    MissingParams,
+    /// A capture isn't valid utf-8 once percent decoded
+    InvalidEncoding(String),
+    /// A capture couldn't be parsed, so the path doesn't point to anything
+    Invalid(String),
+    /// The captured type doesn't fit the route pattern
+    Mismatch(String),
+}
+
+impl PathRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidEncoding(_) => StatusCode::BAD_REQUEST,
+            Self::Invalid(_) => StatusCode::NOT_FOUND,
+            Self::MissingParams | Self::Mismatch(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Display for PathRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingParams => write!(f, "Missing URI Path Params"),
+            Self::InvalidEncoding(key) => write!(f, "Capture `{}` is not valid utf-8", key),
+            Self::Invalid(message) => write!(f, "Invalid path capture: {}", message),
+            Self::Mismatch(message) => write!(f, "Captures don't match the route pattern: {}", message),
+        }
+    }
+}
+
+/// Rejection for the [`Multipart`](super::Multipart) extractor
+#[derive(Debug)]
+pub enum MultipartRejection {
+    /// The request isn't `multipart/form-data` or is missing the boundary
+    UnsupportedMediaType,
+    /// A field or the whole body is over the size limit
+    TooLarge(multer::Error),
+    /// The body isn't valid `multipart/form-data`
+    Malformed(multer::Error),
+    /// The fields don't match the expected form
+    Invalid(crate::Error),
+}
+
+impl MultipartRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Malformed(_) => StatusCode::BAD_REQUEST,
+            Self::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl From<multer::Error> for MultipartRejection {
+    fn from(error: multer::Error) -> Self {
+        match error {
+            multer::Error::FieldSizeExceeded { .. } | multer::Error::StreamSizeExceeded { .. } => Self::TooLarge(error),
+            error => Self::Malformed(error),
+        }
+    }
+}
+
+impl Display for MultipartRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedMediaType => write!(f, "Expected a `multipart/form-data` request with a boundary"),
+            Self::TooLarge(error) | Self::Malformed(error) => error.fmt(f),
+            Self::Invalid(error) => write!(f, "Invalid form: {}", error),
+        }
+    }
+}
@@ -1,29 +1,45 @@
-use std::future::Future;
+use std::{convert::Infallible, future::Future};
 
-use http_body_util::BodyExt;
-use hyper::http::request::Parts;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use hyper::{body::Bytes, header, http::request::Parts, HeaderMap, Method, Uri, Version};
 #[allow(unused_imports)]
 pub use cookie::{Cookie, PrivateJar, SignedJar};
 
-use crate::Error;
-
 use crate::Request;
 
-use super::CookieJar;
+use super::{rejection::BodyRejection, CookieJar, IntoResponse};
 
 pub trait FromParts: Sized {
-    fn from_parts(parts: &Parts, jar: CookieJar) -> impl Future<Output = Result<Self, Error>> + Send;
+    /// Response used when the value can't be extracted
+    type Rejection: IntoResponse;
+
+    fn from_parts(parts: &Parts, jar: CookieJar) -> impl Future<Output = Result<Self, Self::Rejection>> + Send;
 }
 
-impl<T: FromParts> FromParts for Option<T> {
-    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Error> {
-        match T::from_parts(parts, jar).await {
-            Ok(t) => Ok(Some(t)),
-            Err(e) => {
-                log::error!("Error while parsing handler params: {}", e);
-                Ok(None)
-            },
-        }
+/// Extractors that can be used as an `Option<T>` handler argument.
+///
+/// `None` means the value is absent from the request, a value that is there but malformed is
+/// still rejected.
+pub trait OptionalFromParts: Sized {
+    type Rejection: IntoResponse;
+
+    fn from_parts(parts: &Parts, jar: CookieJar) -> impl Future<Output = Result<Option<Self>, Self::Rejection>> + Send;
+}
+
+impl<T: OptionalFromParts> FromParts for Option<T> {
+    type Rejection = T::Rejection;
+
+    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        <T as OptionalFromParts>::from_parts(parts, jar).await
+    }
+}
+
+/// Get the rejection instead of responding with it, so the handler can decide what to do
+impl<T: FromParts> FromParts for Result<T, T::Rejection> {
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        Ok(T::from_parts(parts, jar).await)
     }
 }
 
@@ -31,36 +47,133 @@ pub struct ViaRequest;
 pub struct ViaParts;
 
 pub trait FromRequest<M = ViaRequest>: Sized {
-    fn from_request(request: Request, jar: CookieJar) -> impl Future<Output = Result<Self, Error>> + Send;
+    /// Response used when the value can't be extracted
+    type Rejection: IntoResponse;
+
+    fn from_request(request: Request, jar: CookieJar) -> impl Future<Output = Result<Self, Self::Rejection>> + Send;
 }
 
-impl<T: FromRequest> FromRequest for Option<T> {
-    async fn from_request(request: Request, jar: CookieJar) -> Result<Self, Error> {
-        match T::from_request(request, jar).await {
-            Ok(t) => Ok(Some(t)),
-            Err(e) => {
-                log::error!("Error while parsing handler params: {}", e);
-                Ok(None)
-            },
-        }
+/// Body extractors that can be used as an `Option<T>` handler argument.
+///
+/// `None` means the request has no body, a body that is there but malformed is still rejected.
+pub trait OptionalFromRequest: Sized {
+    type Rejection: IntoResponse;
+
+    fn from_request(request: Request, jar: CookieJar) -> impl Future<Output = Result<Option<Self>, Self::Rejection>> + Send;
+}
+
+impl<T: OptionalFromRequest> FromRequest for Option<T> {
+    type Rejection = T::Rejection;
+
+    async fn from_request(request: Request, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        <T as OptionalFromRequest>::from_request(request, jar).await
+    }
+}
+
+/// Get the rejection instead of responding with it, so the handler can decide what to do
+impl<T: FromRequest> FromRequest for Result<T, T::Rejection> {
+    type Rejection = Infallible;
+
+    async fn from_request(request: Request, jar: CookieJar) -> Result<Self, Self::Rejection> {
+        Ok(T::from_request(request, jar).await)
     }
 }
 
 impl<T: FromParts> FromRequest<ViaParts> for T {
-    async fn from_request(request: Request, jar: CookieJar) -> Result<Self, Error> {
+    type Rejection = T::Rejection;
+
+    async fn from_request(request: Request, jar: CookieJar) -> Result<Self, Self::Rejection> {
         T::from_parts(&request.into_parts().0, jar).await
     }
 }
 
+macro_rules! impl_from_parts_clone {
+    ($($ty: ty => $field: ident),* $(,)?) => {
+        $(
+            impl FromParts for $ty {
+                type Rejection = Infallible;
+
+                async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+                    Ok(parts.$field.clone())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_parts_clone!(Method => method, Uri => uri, Version => version, HeaderMap => headers);
+
+/// The whole request head, the body can't be extracted after this
+impl FromParts for Parts {
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        Ok(parts.clone())
+    }
+}
+
 impl FromRequest for Request {
-    async fn from_request(request: Request, _: CookieJar) -> Result<Self, Error> {
+    type Rejection = Infallible;
+
+    async fn from_request(request: Request, _: CookieJar) -> Result<Self, Self::Rejection> {
         Ok(request)
     }
 }
 
+/// Size limit used by the body extractors when the router or route doesn't set one, 2 MiB
+pub const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Maximum size of the request body, kept in the request extensions. `None` disables the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BodyLimit(pub Option<usize>);
+
+impl Default for BodyLimit {
+    fn default() -> Self {
+        Self(Some(DEFAULT_BODY_LIMIT))
+    }
+}
+
+/// Collect the whole request body, up to the body limit of the request
+pub(crate) async fn collect(request: Request) -> Result<Bytes, BodyRejection> {
+    let limit = match request.extensions().get::<BodyLimit>().copied().unwrap_or_default() {
+        BodyLimit(Some(limit)) => limit,
+        BodyLimit(None) => return Ok(request.into_body().collect().await.map_err(BodyRejection::Read)?.to_bytes()),
+    };
+
+    // Don't start reading a body that is announced to be too large
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if length.is_some_and(|length| length > limit as u64) {
+        return Err(BodyRejection::TooLarge(limit));
+    }
+
+    match Limited::new(request.into_body(), limit).collect().await {
+        Ok(body) => Ok(body.to_bytes()),
+        Err(error) if error.is::<LengthLimitError>() => Err(BodyRejection::TooLarge(limit)),
+        Err(error) => Err(BodyRejection::Read(error)),
+    }
+}
+
 // This allows for for the last parameter to collect the body as a string
 impl FromRequest for String {
-    async fn from_request(request: Request, _: CookieJar) -> Result<Self, Error> {
-        Ok(String::from_utf8(request.collect().await.unwrap().to_bytes().to_vec())?)
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: Request, _: CookieJar) -> Result<Self, Self::Rejection> {
+        String::from_utf8(collect(request).await?.to_vec()).map_err(BodyRejection::InvalidUtf8)
+    }
+}
+
+impl OptionalFromRequest for String {
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: Request, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        let body = collect(request).await?;
+        if body.is_empty() {
+            return Ok(None);
+        }
+        String::from_utf8(body.to_vec()).map(Some).map_err(BodyRejection::InvalidUtf8)
     }
 }
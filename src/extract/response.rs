@@ -1,4 +1,4 @@
-use std::{borrow::Cow, convert::Infallible};
+use std::{borrow::Cow, convert::Infallible, sync::Arc};
 
 use hyper::http::response::Parts;
 use http_body_util::{Empty, Full};
@@ -103,11 +103,23 @@ macro_rules! impl_into_response_parts {
 
 all_variants!(impl_into_response_parts);
 
+/// The error that produced a response. Kept in the response extensions so catchers can inspect
+/// it.
+#[derive(Clone)]
+pub(crate) struct ResponseError(pub Arc<crate::Error>);
+
+/// A boxed [`HttpError`](crate::HttpError) keeps its status, everything else is a
+/// `500 Internal Server Error`.
 impl IntoResponse for crate::Error {
     fn into_response(self) -> Response {
-        log::error!("{}", self);
+        let error = match crate::HttpError::classify(self) {
+            Ok(error) => return error.into_response(),
+            Err(error) => error,
+        };
+        log::error!("{}", error);
         Response::builder()
             .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .extension(ResponseError(Arc::new(error)))
             .body(Body::empty())
             .unwrap()
     }
@@ -122,6 +134,19 @@ impl IntoResponse for Infallible {
     }
 }
 
+impl<T, E> IntoResponse for Result<T, E>
+where
+    T: IntoResponse,
+    E: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
 impl IntoResponse for () {
     fn into_response(self) -> Response {
         Response::new(Body::empty())
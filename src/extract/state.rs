@@ -0,0 +1,110 @@
+use std::{any::type_name, convert::Infallible, fmt::Display, ops::{Deref, DerefMut}, sync::Arc};
+
+use hyper::{http::{request::Parts, Extensions}, StatusCode};
+
+use crate::Response;
+
+use super::{rejection::reject, request::{FromParts, OptionalFromParts}, CookieJar, IntoResponse};
+
+/// Used to create a sub state from a routers state.
+///
+/// This lets handlers extract only the part of the application state they need with
+/// [`State`]. Sub states are registered with [`PathRouter::with_substate`](crate::server::PathRouter::with_substate).
+pub trait FromRef<S> {
+    fn from_ref(input: &S) -> Self;
+}
+
+impl<T: Clone> FromRef<T> for T {
+    fn from_ref(input: &T) -> Self {
+        input.clone()
+    }
+}
+
+/// Wrapper for state stored in the request extensions so it can't collide with other extensions
+/// of the same type.
+#[derive(Clone)]
+pub(crate) struct Shared<T>(pub T);
+
+/// The state of a router a request went through, shared with every request instead of copied into
+/// them. Nested routers keep the state of the routers around them in `outer`.
+#[derive(Clone)]
+pub(crate) struct RouterState {
+    pub state: Arc<Extensions>,
+    pub outer: Option<Arc<RouterState>>,
+}
+
+/// Find a state, the innermost router that has it wins over the routers around it and over state
+/// that was added to the request extensions directly.
+pub(crate) fn shared<T: Send + Sync + 'static>(extensions: &Extensions) -> Option<&T> {
+    let mut router = extensions.get::<RouterState>();
+    while let Some(RouterState { state, outer }) = router {
+        if let Some(Shared(state)) = state.get::<Shared<T>>() {
+            return Some(state);
+        }
+        router = outer.as_deref();
+    }
+    extensions.get::<Shared<T>>().map(|Shared(state)| state)
+}
+
+#[derive(Debug)]
+pub struct MissingState(pub(crate) &'static str);
+impl Display for MissingState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Missing state `{}`; make sure it is added to the router with `with_state` or `with_substate`",
+            self.0
+        )
+    }
+}
+impl std::error::Error for MissingState {}
+
+impl IntoResponse for MissingState {
+    fn into_response(self) -> Response {
+        reject(StatusCode::INTERNAL_SERVER_ERROR, self)
+    }
+}
+
+/// Extract shared application state that was added to the router with
+/// [`PathRouter::with_state`](crate::server::PathRouter::with_state).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct State<T>(pub T);
+
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for State<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> FromParts for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingState;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        match shared::<T>(&parts.extensions) {
+            Some(state) => Ok(State(state.clone())),
+            None => Err(MissingState(type_name::<T>())),
+        }
+    }
+}
+
+impl<T> OptionalFromParts for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(shared::<T>(&parts.extensions).map(|state| State(state.clone())))
+    }
+}
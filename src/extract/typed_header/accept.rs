@@ -0,0 +1,168 @@
+use headers::{Error, Header};
+use hyper::header::{self, HeaderName, HeaderValue};
+use mime::Mime;
+
+/// A media range from the `Accept` header, like `text/*;q=0.8`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRange {
+    mime: Mime,
+    quality: f32,
+}
+
+impl MediaRange {
+    /// The quality is clamped between `0` and `1`
+    pub fn new(mime: Mime, quality: f32) -> Self {
+        Self { mime, quality: quality.clamp(0.0, 1.0) }
+    }
+
+    /// The media range without the `q` parameter
+    pub fn mime(&self) -> &Mime {
+        &self.mime
+    }
+
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+
+    /// Whether the media type is part of this range, parameters other than `q` are ignored
+    pub fn matches(&self, mime: &Mime) -> bool {
+        (self.mime.type_() == mime::STAR || self.mime.type_() == mime.type_())
+            && (self.mime.subtype() == mime::STAR || self.mime.subtype() == mime.subtype())
+    }
+
+    /// `*/*` is the least specific, followed by ranges like `text/*`
+    fn specificity(&self) -> u8 {
+        match (self.mime.type_() == mime::STAR, self.mime.subtype() == mime::STAR) {
+            (true, _) => 0,
+            (false, true) => 1,
+            (false, false) => 2,
+        }
+    }
+
+    fn parse(range: &str) -> Result<Self, Error> {
+        let parsed = range.parse::<Mime>().map_err(|_| Error::invalid())?;
+        let quality = match parsed.get_param("q") {
+            Some(quality) => quality.as_str().parse::<f32>().map_err(|_| Error::invalid())?,
+            None => 1.0,
+        };
+        if !(0.0..=1.0).contains(&quality) {
+            return Err(Error::invalid());
+        }
+
+        // Rebuild the media range without the quality parameter
+        let params = parsed
+            .params()
+            .filter(|(name, _)| *name != "q")
+            .map(|(name, value)| format!("; {}={}", name, value))
+            .collect::<String>();
+        let mime = match params.is_empty() {
+            true => parsed.essence_str().parse(),
+            false => format!("{}{}", parsed.essence_str(), params).parse(),
+        };
+        Ok(Self { mime: mime.map_err(|_| Error::invalid())?, quality })
+    }
+}
+
+/// The `Accept` header, the media types a client can handle ordered by preference.
+///
+/// ```ignore
+/// async fn report(TypedHeader(accept): TypedHeader<Accept>) -> Response {
+///     match accept.negotiate(&[mime::APPLICATION_JSON, mime::TEXT_CSV]) { ... }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accept(Vec<MediaRange>);
+
+impl Accept {
+    /// Media ranges from the most to the least preferred
+    pub fn iter(&self) -> impl Iterator<Item = &MediaRange> {
+        self.0.iter()
+    }
+
+    /// Quality of a media type, from the most specific range that matches it. `0` when the
+    /// media type isn't accepted.
+    pub fn quality(&self, mime: &Mime) -> f32 {
+        self.0
+            .iter()
+            .filter(|range| range.matches(mime))
+            .max_by_key(|range| range.specificity())
+            .map(MediaRange::quality)
+            .unwrap_or(0.0)
+    }
+
+    pub fn accepts(&self, mime: &Mime) -> bool {
+        self.quality(mime) > 0.0
+    }
+
+    /// Like [`accepts`](Self::accepts), but a blanket `*/*` only counts when the client didn't
+    /// ask for anything more specific. The media type can be a range like `text/*`, which is
+    /// accepted when any media type in it is.
+    pub fn explicitly_accepts(&self, mime: &Mime) -> bool {
+        let specific = self.0.iter().any(|range| range.specificity() > 0);
+        let mut ranges = self.0.iter().filter(|range| !specific || range.specificity() > 0);
+        if mime.type_() == mime::STAR || mime.subtype() == mime::STAR {
+            let wanted = MediaRange::new(mime.clone(), 1.0);
+            return ranges.any(|range| range.quality > 0.0 && (range.matches(mime) || wanted.matches(&range.mime)));
+        }
+        ranges
+            .filter(|range| range.matches(mime))
+            .max_by_key(|range| range.specificity())
+            .is_some_and(|range| range.quality > 0.0)
+    }
+
+    /// The available media type the client prefers, the first one wins when they are equally
+    /// preferred. `None` when none of them are accepted.
+    pub fn negotiate<'a>(&self, available: &'a [Mime]) -> Option<&'a Mime> {
+        let mut best: Option<(&Mime, f32)> = None;
+        for mime in available {
+            let quality = self.quality(mime);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((mime, quality));
+            }
+        }
+        best.map(|(mime, _)| mime)
+    }
+}
+
+impl FromIterator<MediaRange> for Accept {
+    fn from_iter<T: IntoIterator<Item = MediaRange>>(iter: T) -> Self {
+        let mut ranges = iter.into_iter().collect::<Vec<_>>();
+        // Stable sort so ranges that are equally preferred keep the order of the client
+        ranges.sort_by(|a, b| b.quality.total_cmp(&a.quality).then(b.specificity().cmp(&a.specificity())));
+        Self(ranges)
+    }
+}
+
+impl Header for Accept {
+    fn name() -> &'static HeaderName {
+        &header::ACCEPT
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let mut ranges = Vec::new();
+        for value in values {
+            let value = value.to_str().map_err(|_| Error::invalid())?;
+            for range in value.split(',').map(str::trim).filter(|range| !range.is_empty()) {
+                ranges.push(MediaRange::parse(range)?);
+            }
+        }
+        Ok(ranges.into_iter().collect())
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        let value = self.0
+            .iter()
+            .map(|range| match range.quality < 1.0 {
+                true => format!("{};q={}", range.mime, (range.quality * 1000.0).round() / 1000.0),
+                false => range.mime.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            values.extend(std::iter::once(value));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use headers::{Error, Header};
+use hyper::header::{self, HeaderName, HeaderValue};
+
+/// One proxy hop from the `Forwarded` header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedElement {
+    by: Option<String>,
+    for_: Option<String>,
+    host: Option<String>,
+    proto: Option<String>,
+}
+
+impl ForwardedElement {
+    /// The interface where the request came in to the proxy
+    pub fn by(&self) -> Option<&str> {
+        self.by.as_deref()
+    }
+
+    /// The client that made the request to the proxy
+    pub fn client(&self) -> Option<&str> {
+        self.for_.as_deref()
+    }
+
+    /// The `Host` header the proxy received
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// The protocol used to make the request to the proxy, like `https`
+    pub fn proto(&self) -> Option<&str> {
+        self.proto.as_deref()
+    }
+
+    pub fn with_by<S: Into<String>>(mut self, by: S) -> Self {
+        self.by = Some(by.into());
+        self
+    }
+
+    pub fn with_client<S: Into<String>>(mut self, client: S) -> Self {
+        self.for_ = Some(client.into());
+        self
+    }
+
+    pub fn with_host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    pub fn with_proto<S: Into<String>>(mut self, proto: S) -> Self {
+        self.proto = Some(proto.into());
+        self
+    }
+
+    fn parse(element: &str) -> Result<Self, Error> {
+        let mut parsed = Self::default();
+        for pair in split_unquoted(element, ';').into_iter().map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(Error::invalid)?;
+            let value = unquote(value.trim())?;
+            match key.trim().to_ascii_lowercase().as_str() {
+                "by" => parsed.by = Some(value),
+                "for" => parsed.for_ = Some(value),
+                "host" => parsed.host = Some(value),
+                "proto" => parsed.proto = Some(value),
+                // Extensions aren't used
+                _ => {},
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn encode(&self) -> String {
+        [("by", &self.by), ("for", &self.for_), ("host", &self.host), ("proto", &self.proto)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, quote(value))))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+/// The RFC 7239 `Forwarded` header, one element for every proxy the request went through.
+///
+/// Only trust the values that were added by your own proxies, clients can send any value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Forwarded(Vec<ForwardedElement>);
+
+impl Forwarded {
+    /// Elements in the order the proxies added them, the first one is closest to the client
+    pub fn elements(&self) -> &[ForwardedElement] {
+        &self.0
+    }
+
+    /// The client the first proxy received the request from
+    pub fn client(&self) -> Option<&str> {
+        self.0.first().and_then(ForwardedElement::client)
+    }
+
+    /// The `Host` the first proxy received
+    pub fn host(&self) -> Option<&str> {
+        self.0.first().and_then(ForwardedElement::host)
+    }
+
+    /// The protocol the first proxy received the request with
+    pub fn proto(&self) -> Option<&str> {
+        self.0.first().and_then(ForwardedElement::proto)
+    }
+}
+
+impl FromIterator<ForwardedElement> for Forwarded {
+    fn from_iter<T: IntoIterator<Item = ForwardedElement>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Split on a separator that isn't inside a quoted string
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> Result<String, Error> {
+    let inner = match value.strip_prefix('"') {
+        Some(inner) => inner.strip_suffix('"').ok_or_else(Error::invalid)?,
+        None => return Ok(value.to_string()),
+    };
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.push(chars.next().ok_or_else(Error::invalid)?),
+            c => unquoted.push(c),
+        }
+    }
+    Ok(unquoted)
+}
+
+/// Values like IPv6 addresses and ports aren't tokens and have to be quoted
+fn quote(value: &str) -> String {
+    let token = !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+    match token {
+        true => value.to_string(),
+        false => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+impl Header for Forwarded {
+    fn name() -> &'static HeaderName {
+        &header::FORWARDED
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let mut elements = Vec::new();
+        for value in values {
+            let value = value.to_str().map_err(|_| Error::invalid())?;
+            for element in split_unquoted(value, ',').into_iter().filter(|element| !element.trim().is_empty()) {
+                elements.push(ForwardedElement::parse(element)?);
+            }
+        }
+        if elements.is_empty() {
+            return Err(Error::invalid());
+        }
+        Ok(Self(elements))
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        let value = self.0.iter().map(ForwardedElement::encode).collect::<Vec<_>>().join(", ");
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            values.extend(std::iter::once(value));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::{convert::Infallible, fmt::Display, ops::Deref};
+
+use headers::{Header, HeaderMapExt};
+use hyper::{header::HeaderName, http::request::Parts, StatusCode};
+
+use crate::Response;
+
+mod accept;
+mod forwarded;
+
+use super::{rejection::reject, request::{FromParts, OptionalFromParts}, CookieJar, IntoResponse, IntoResponseParts};
+
+/// Strongly typed headers, from the [`headers`](::headers) crate with the addition of
+/// [`Accept`](headers::Accept) and [`Forwarded`](headers::Forwarded).
+pub mod headers {
+    pub use ::headers::*;
+    pub use super::accept::{Accept, MediaRange};
+    pub use super::forwarded::{Forwarded, ForwardedElement};
+}
+
+/// Extract a typed header, or add one to a response.
+///
+/// ```ignore
+/// async fn profile(
+///     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+///     agent: Option<TypedHeader<UserAgent>>,
+/// ) -> (TypedHeader<CacheControl>, Json<Profile>) { ... }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedHeader<H>(pub H);
+
+impl<H> Deref for TypedHeader<H> {
+    type Target = H;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<H: Header + Send> FromParts for TypedHeader<H> {
+    type Rejection = TypedHeaderRejection;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        match parts.headers.typed_try_get::<H>() {
+            Ok(Some(header)) => Ok(TypedHeader(header)),
+            Ok(None) => Err(TypedHeaderRejection::Missing(H::name())),
+            Err(_) => Err(TypedHeaderRejection::Invalid(H::name())),
+        }
+    }
+}
+
+/// `None` when the header isn't in the request, an invalid header is still rejected
+impl<H: Header + Send> OptionalFromParts for TypedHeader<H> {
+    type Rejection = TypedHeaderRejection;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        parts.headers
+            .typed_try_get::<H>()
+            .map(|header| header.map(TypedHeader))
+            .map_err(|_| TypedHeaderRejection::Invalid(H::name()))
+    }
+}
+
+impl<H: Header> IntoResponseParts for TypedHeader<H> {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: Response) -> Result<Response, Self::Error> {
+        res.headers_mut().typed_insert(self.0);
+        Ok(res)
+    }
+}
+
+impl<H: Header> IntoResponse for TypedHeader<H> {
+    fn into_response(self) -> Response {
+        let mut response = ().into_response();
+        response.headers_mut().typed_insert(self.0);
+        response
+    }
+}
+
+/// Rejection for the [`TypedHeader`] extractor
+#[derive(Debug)]
+pub enum TypedHeaderRejection {
+    Missing(&'static HeaderName),
+    /// The header couldn't be parsed
+    Invalid(&'static HeaderName),
+}
+
+impl TypedHeaderRejection {
+    pub fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    pub fn name(&self) -> &'static HeaderName {
+        match self {
+            Self::Missing(name) | Self::Invalid(name) => name,
+        }
+    }
+}
+
+impl Display for TypedHeaderRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "Missing the `{}` header", name),
+            Self::Invalid(name) => write!(f, "Invalid `{}` header", name),
+        }
+    }
+}
+
+impl std::error::Error for TypedHeaderRejection {}
+
+impl IntoResponse for TypedHeaderRejection {
+    fn into_response(self) -> Response {
+        reject(self.status(), self)
+    }
+}
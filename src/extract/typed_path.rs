@@ -0,0 +1,75 @@
+use std::fmt::Display;
+
+use hyper::Uri;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Everything besides the characters RFC 3986 allows in a path segment is encoded, so the path is
+/// always a valid uri
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-').remove(b'.').remove(b'_').remove(b'~')
+    .remove(b'!').remove(b'$').remove(b'&').remove(b'\'').remove(b'(').remove(b')')
+    .remove(b'*').remove(b'+').remove(b',').remove(b';').remove(b'=').remove(b':').remove(b'@');
+
+/// Catch all captures can span multiple segments
+const CATCH_ALL: &AsciiSet = &SEGMENT.remove(b'/');
+
+/// A route path represented by a struct, so routing, extraction and links share the route pattern.
+///
+/// Derive it with `#[derive(TypedPath)]` and a `#[typed_path("...")]` attribute. The attribute
+/// isn't called `path` since that name is taken by the builtin `#[path = "..."]` attribute for
+/// modules. The fields of the struct are the captures of the pattern, and the struct can be used as
+/// an extractor. It also needs to implement `Deserialize`.
+///
+/// ```ignore
+/// #[derive(Deserialize, TypedPath)]
+/// #[typed_path("/users/:id/posts/:post")]
+/// struct UserPost { id: u32, post: String }
+///
+/// async fn post(path: UserPost) -> Html<String> { ... }
+///
+/// PathRouter::default().route(UserPost::PATH, methods::get(post));
+///
+/// let link = UserPost { id: 1, post: "hello".into() }.to_string(); // `/users/1/posts/hello`
+/// ```
+pub trait TypedPath: Display {
+    /// The route pattern
+    const PATH: &'static str;
+
+    /// The path with every capture filled in
+    fn to_uri(&self) -> Uri {
+        // Captures are percent encoded and the derive only accepts valid literal segments
+        self.to_string().parse().expect("TypedPath produced an invalid uri")
+    }
+}
+
+#[doc(hidden)]
+pub fn __encode<T: Display>(value: &T, catch_all: bool) -> String {
+    let value = value.to_string();
+    let set = if catch_all { CATCH_ALL } else { SEGMENT };
+    utf8_percent_encode(value.as_str(), set).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Post(String);
+
+    impl Display for Post {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/posts/{}", __encode(&self.0, false))
+        }
+    }
+
+    impl TypedPath for Post {
+        const PATH: &'static str = "/posts/:slug";
+    }
+
+    #[test]
+    fn captures_are_encoded() {
+        let uri = Post("a b/\\\"^|[]{}?#%ü".into()).to_uri();
+        assert_eq!(uri.path(), "/posts/a%20b%2F%5C%22%5E%7C%5B%5D%7B%7D%3F%23%25%C3%BC");
+        assert_eq!(__encode(&"a/b c", true), "a/b%20c");
+        assert_eq!(__encode(&"it's:me@home", false), "it's:me@home");
+    }
+}
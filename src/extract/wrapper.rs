@@ -1,11 +1,11 @@
 use std::{fmt::Debug, collections::HashMap};
-use http_body_util::BodyExt;
 use hyper::{header, http::request::Parts};
+use mime::Mime;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::Response;
 
-use super::{request::{FromParts, FromRequest}, IntoResponse};
+use super::{rejection::{JsonRejection, QueryRejection, UrlEncodedRejection}, request::{collect, FromParts, FromRequest, OptionalFromParts, OptionalFromRequest}, IntoResponse};
 
 /// Light wrapper around `IntoResponse` to set the `Content-Type` header to `text/html`.
 pub struct Html<T>(pub T);
@@ -23,10 +23,70 @@ impl<T: Debug> Debug for Json<T> {
         f.debug_struct("Json").field("inner", &self.0).finish()
     }
 }
+/// Check the media type of the request, ignoring parameters like `charset`. `None` when the
+/// request has no `Content-Type`.
+fn content_type(request: &crate::Request, matches: fn(&Mime) -> bool) -> Option<bool> {
+    let content_type = request.headers().get(header::CONTENT_TYPE)?;
+    Some(content_type
+        .to_str()
+        .ok()
+        .and_then(|content_type| content_type.parse::<Mime>().ok())
+        .is_some_and(|mime| matches(&mime)))
+}
+
+/// `application/json` or any `+json` media type like `application/merge-patch+json`
+fn is_json(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION && (mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+}
+
+fn is_urlencoded(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION && mime.subtype() == mime::WWW_FORM_URLENCODED
+}
+
+/// Parse json keeping track of where in the document an error happened
+fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, JsonRejection> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|error| {
+        let path = error.path().to_string();
+        let error = error.into_inner();
+        match error.classify() {
+            serde_json::error::Category::Data => JsonRejection::Data { path, error },
+            _ => JsonRejection::Syntax(error),
+        }
+    })?;
+    // Only whitespace is allowed after the value
+    deserializer.end().map_err(JsonRejection::Syntax)?;
+    Ok(value)
+}
+
+/// Requires an `application/json` or `+json` request, use [`Lenient`] to accept any `Content-Type`
 impl<T: DeserializeOwned> FromRequest for Json<T> {
-    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, crate::Error> {
-        let body = String::from_utf8(request.collect().await?.to_bytes().to_vec())?;
-        Ok(Json(serde_json::from_str::<T>(body.as_str())?))
+    type Rejection = JsonRejection;
+
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, Self::Rejection> {
+        if content_type(&request, is_json) != Some(true) {
+            return Err(JsonRejection::UnsupportedMediaType);
+        }
+        let body = collect(request).await.map_err(JsonRejection::Body)?;
+        parse_json(&body).map(Json)
+    }
+}
+
+/// `None` when the request has no body and no `Content-Type`
+impl<T: DeserializeOwned> OptionalFromRequest for Json<T> {
+    type Rejection = JsonRejection;
+
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        let expected = content_type(&request, is_json);
+        if expected == Some(false) {
+            return Err(JsonRejection::UnsupportedMediaType);
+        }
+        let body = collect(request).await.map_err(JsonRejection::Body)?;
+        match expected {
+            Some(_) => parse_json(&body).map(|value| Some(Json(value))),
+            None if body.is_empty() => Ok(None),
+            None => Err(JsonRejection::UnsupportedMediaType),
+        }
     }
 }
 
@@ -55,14 +115,26 @@ impl<T: Debug> Debug for Query<T> {
     }
 }
 impl<T: DeserializeOwned> FromParts for Query<T> {
-    async fn from_parts(parts: &Parts, _jar: super::CookieJar) -> Result<Self, crate::Error> {
+    type Rejection = QueryRejection;
+
+    /// A request without a query string is treated as an empty query
+    async fn from_parts(parts: &Parts, _jar: super::CookieJar) -> Result<Self, Self::Rejection> {
+        serde_urlencoded::from_str::<T>(parts.uri.query().unwrap_or_default())
+            .map(Query)
+            .map_err(QueryRejection::Deserialize)
+    }
+}
+
+/// `None` when the request has no query string
+impl<T: DeserializeOwned> OptionalFromParts for Query<T> {
+    type Rejection = QueryRejection;
+
+    async fn from_parts(parts: &Parts, _jar: super::CookieJar) -> Result<Option<Self>, Self::Rejection> {
         match parts.uri.query() {
-            Some(query) => {
-                let query = query.to_string();
-                println!("QUERY: {}", query);
-                Ok(Query(serde_urlencoded::from_str::<T>(query.as_str())?))
-            },
-            None => Err("(400 BAD REQUEST) No query string found".to_string().into()),
+            Some(query) => serde_urlencoded::from_str::<T>(query)
+                .map(|query| Some(Query(query)))
+                .map_err(QueryRejection::Deserialize),
+            None => Ok(None),
         }
     }
 }
@@ -73,10 +145,71 @@ impl<T: Debug> Debug for UrlEncoded<T> {
         f.debug_struct("UrlEncoded").field("inner", &self.0).finish()
     }
 }
+/// Requires an `application/x-www-form-urlencoded` request, use [`Lenient`] to accept any
+/// `Content-Type`
 impl<T: DeserializeOwned> FromRequest for UrlEncoded<T> {
-    async fn from_request(request: crate::Request, _: super::CookieJar) -> Result<Self, crate::Error> {
-        let body = String::from_utf8(request.into_body().collect().await?.to_bytes().to_vec())?;
-        Ok(UrlEncoded(serde_urlencoded::from_str::<T>(body.as_str())?))
+    type Rejection = UrlEncodedRejection;
+
+    async fn from_request(request: crate::Request, _: super::CookieJar) -> Result<Self, Self::Rejection> {
+        if content_type(&request, is_urlencoded) != Some(true) {
+            return Err(UrlEncodedRejection::UnsupportedMediaType);
+        }
+        let body = collect(request).await.map_err(UrlEncodedRejection::Body)?;
+        serde_urlencoded::from_bytes::<T>(&body)
+            .map(UrlEncoded)
+            .map_err(UrlEncodedRejection::Deserialize)
+    }
+}
+
+/// `None` when the request has no body and no `Content-Type`
+impl<T: DeserializeOwned> OptionalFromRequest for UrlEncoded<T> {
+    type Rejection = UrlEncodedRejection;
+
+    async fn from_request(request: crate::Request, _: super::CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        let expected = content_type(&request, is_urlencoded);
+        if expected == Some(false) {
+            return Err(UrlEncodedRejection::UnsupportedMediaType);
+        }
+        let body = collect(request).await.map_err(UrlEncodedRejection::Body)?;
+        match expected {
+            Some(_) => serde_urlencoded::from_bytes::<T>(&body)
+                .map(|form| Some(UrlEncoded(form)))
+                .map_err(UrlEncodedRejection::Deserialize),
+            None if body.is_empty() => Ok(None),
+            None => Err(UrlEncodedRejection::UnsupportedMediaType),
+        }
+    }
+}
+
+/// Parse a body whatever it's `Content-Type` is, for clients that don't send the right one.
+///
+/// ```ignore
+/// async fn legacy(Lenient(Json(user)): Lenient<Json<User>>) { ... }
+/// ```
+pub struct Lenient<T>(pub T);
+impl<T: Debug> Debug for Lenient<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lenient").field("inner", &self.0).finish()
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Lenient<Json<T>> {
+    type Rejection = JsonRejection;
+
+    async fn from_request(request: crate::Request, _jar: super::CookieJar) -> Result<Self, Self::Rejection> {
+        let body = collect(request).await.map_err(JsonRejection::Body)?;
+        parse_json(&body).map(|value| Lenient(Json(value)))
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Lenient<UrlEncoded<T>> {
+    type Rejection = UrlEncodedRejection;
+
+    async fn from_request(request: crate::Request, _: super::CookieJar) -> Result<Self, Self::Rejection> {
+        let body = collect(request).await.map_err(UrlEncodedRejection::Body)?;
+        serde_urlencoded::from_bytes::<T>(&body)
+            .map(|form| Lenient(UrlEncoded(form)))
+            .map_err(UrlEncodedRejection::Deserialize)
     }
 }
 
@@ -0,0 +1,62 @@
+use std::task::{Context, Poll};
+
+use tower::{Layer, Service};
+
+use crate::{extract::BodyLimit, Request};
+
+/// Set the maximum body size for the `Json`, `UrlEncoded`, `String` and `Bytes` extractors of the
+/// wrapped routes. Larger bodies are rejected with `413 Payload Too Large`.
+///
+/// This overrides the limit of the router, which is
+/// [`DEFAULT_BODY_LIMIT`](crate::extract::DEFAULT_BODY_LIMIT) unless it is changed with
+/// [`PathRouter::body_limit`](crate::server::PathRouter::body_limit).
+///
+/// ```ignore
+/// PathRouter::default()
+///     .route("/upload", post(upload.layer(BodyLimitLayer::new(64 * 1024 * 1024))))
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimitLayer {
+    limit: Option<usize>,
+}
+
+impl BodyLimitLayer {
+    /// Limit the body to `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Self { limit: Some(limit) }
+    }
+
+    /// Don't limit the body size
+    pub fn disable() -> Self {
+        Self { limit: None }
+    }
+}
+
+impl<S> Layer<S> for BodyLimitLayer {
+    type Service = BodyLimitService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        BodyLimitService { limit: self.limit, service }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BodyLimitService<S> {
+    limit: Option<usize>,
+    service: S,
+}
+
+impl<S: Service<Request>> Service<Request> for BodyLimitService<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        req.extensions_mut().insert(BodyLimit(self.limit));
+        self.service.call(req)
+    }
+}
@@ -1,2 +1,4 @@
 mod logging;
+mod body_limit;
 pub use logging::{LogLayer, LogOptions};
+pub use body_limit::{BodyLimitLayer, BodyLimitService};
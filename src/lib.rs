@@ -1,15 +1,19 @@
 use std::{ops::Deref, sync::Arc};
 
 mod body;
+mod error;
 
 pub mod server;
 pub mod layer;
 pub mod extract;
+pub mod openapi;
+pub mod session;
 
 use hyper::body::Bytes;
 pub use mime_guess;
 pub use hyper::{header, StatusCode};
 pub use body::{Body, BoxError};
+pub use error::HttpError;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -107,6 +111,30 @@ pub mod prelude {
     pub use super::ResponseShortcut;
 }
 
+/// Mount routes generated by route attributes like `#[get("/")]`.
+///
+/// Creates a new [`PathRouter`](crate::server::PathRouter), or adds the routes to an existing
+/// one with `routes![router => a, b]`.
+///
+/// ```ignore
+/// #[get("/users/:id")]
+/// async fn user(Capture(id): Capture<u32>) -> String { ... }
+///
+/// #[post("/users")]
+/// async fn create(Json(user): Json<User>) -> StatusCode { ... }
+///
+/// let router = routes![user, create];
+/// ```
+#[macro_export]
+macro_rules! routes {
+    ($router: expr => $($route: path),* $(,)?) => {
+        $router$(.mount::<$route>())*
+    };
+    ($($route: path),* $(,)?) => {
+        $crate::routes!($crate::server::PathRouter::default() => $($route),*)
+    };
+}
+
 #[macro_export]
 macro_rules! all_variants {
     ($macro: ident) => {
@@ -0,0 +1,319 @@
+//! Generate an [OpenAPI 3.1](https://spec.openapis.org/oas/v3.1.0) document from the routes of a
+//! [`PathRouter`](crate::server::PathRouter).
+//!
+//! Handlers are only added to the document when they are wrapped with [`doc`]. The parameters,
+//! request body and responses are derived from the handlers extractors and return type. The error
+//! of a `Result`, like [`HttpError`](crate::HttpError) or [`Problem`](crate::extract::Problem), is
+//! documented as the `default` response.
+//!
+//! ```ignore
+//! #[derive(Deserialize, ToSchema)]
+//! struct Search { q: String, page: Option<u32> }
+//!
+//! async fn search(Query(search): Query<Search>) -> Json<Vec<String>> { ... }
+//!
+//! PathRouter::default()
+//!     .route("/search", methods::get(doc(search).summary("Search everything")))
+//!     .with_openapi("/openapi.json", Some("/docs"), Info::new("Search", "1.0.0"))
+//! ```
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use hyper::Method;
+use serde_json::{json, Map, Value};
+
+mod schema;
+mod operation;
+
+pub use schema::{reference, resolve, Schemas, ToSchema, __private};
+pub use operation::{doc, Documented, OperationHandler, OperationInput, OperationOutput};
+pub use wayfinder_macros::ToSchema;
+
+const SWAGGER_UI: &str = include_str!("swagger.html");
+
+/// Where the Swagger UI page loads it's scripts and styles from, pinned to an exact version of
+/// `swagger-ui-dist`
+pub const SWAGGER_UI_ASSETS: &str = "https://unpkg.com/swagger-ui-dist@5.17.14";
+
+/// The Swagger UI page for the document at `spec`, with the `swagger-ui.css` and
+/// `swagger-ui-bundle.js` of `swagger-ui-dist` served from `assets`.
+///
+/// Serve the assets yourself when the page has to work offline or behind a strict Content
+/// Security Policy, for example with a [`FileRouter`](crate::server::FileRouter).
+pub fn swagger_ui(spec: &str, assets: &str) -> String {
+    SWAGGER_UI.replace("{{spec}}", spec).replace("{{assets}}", assets.trim_end_matches('/'))
+}
+
+/// General information about the API
+#[derive(Debug, Clone)]
+pub struct Info {
+    pub title: String,
+    pub version: String,
+    pub description: Option<String>,
+}
+
+impl Info {
+    pub fn new<T: ToString, V: ToString>(title: T, version: V) -> Self {
+        Self {
+            title: title.to_string(),
+            version: version.to_string(),
+            description: None,
+        }
+    }
+
+    pub fn description<S: ToString>(mut self, description: S) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+}
+
+impl Default for Info {
+    fn default() -> Self {
+        Self::new("API", "0.1.0")
+    }
+}
+
+/// A single operation on a path, for example `GET /users/{id}`.
+#[derive(Debug, Default, Clone)]
+pub struct Operation {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub operation_id: Option<String>,
+    pub tags: Vec<String>,
+    pub(crate) parameters: Vec<Value>,
+    pub(crate) request_body: Option<Value>,
+    pub(crate) responses: BTreeMap<String, Value>,
+    pub(crate) schemas: Schemas,
+}
+
+impl Operation {
+    /// Get the schema for a type, registering any named schemas it uses.
+    pub fn schema<T: ToSchema + ?Sized>(&mut self) -> Value {
+        T::schema(&mut self.schemas)
+    }
+
+    /// Add a parameter. Parameters with the same name and location are replaced.
+    pub fn parameter(&mut self, name: &str, location: &str, required: bool, schema: Value) {
+        self.parameters.retain(|param| !(param["name"] == name && param["in"] == location));
+        self.parameters.push(json!({
+            "name": name,
+            "in": location,
+            "required": required || location == "path",
+            "schema": schema,
+        }));
+    }
+
+    /// Add every property of an object schema as a parameter, used for query strings.
+    pub fn parameters<T: ToSchema + ?Sized>(&mut self, location: &str) {
+        let schema = self.schema::<T>();
+        let schema = resolve(&schema, &self.schemas).clone();
+        let required = schema["required"]
+            .as_array()
+            .map(|required| required.iter().filter_map(Value::as_str).map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        match schema["properties"].as_object() {
+            Some(properties) => for (name, property) in properties {
+                self.parameter(name, location, required.contains(name), property.clone());
+            },
+            // Maps and other free form values are documented as a single object parameter
+            None => self.parameters.push(json!({
+                "name": "params",
+                "in": location,
+                "style": "form",
+                "explode": true,
+                "schema": schema,
+            })),
+        }
+    }
+
+    /// Set the request body
+    pub fn request_body<T: ToSchema + ?Sized>(&mut self, content_type: &str) {
+        let schema = self.schema::<T>();
+        self.request_body = Some(json!({
+            "required": T::required(),
+            "content": { content_type: { "schema": schema } },
+        }));
+    }
+
+    /// Add a response with a body
+    pub fn response<T: ToSchema + ?Sized>(&mut self, status: &str, content_type: &str) {
+        let schema = self.schema::<T>();
+        self.responses.insert(status.to_string(), json!({
+            "description": status_description(status),
+            "content": { content_type: { "schema": schema } },
+        }));
+    }
+
+    /// Add a response without a body
+    pub fn empty_response(&mut self, status: &str) {
+        self.responses.insert(status.to_string(), json!({
+            "description": status_description(status),
+        }));
+    }
+
+    fn to_json(&self, path_params: &[String]) -> Value {
+        let mut op = Map::new();
+        if let Some(summary) = &self.summary {
+            op.insert("summary".into(), summary.as_str().into());
+        }
+        if let Some(description) = &self.description {
+            op.insert("description".into(), description.as_str().into());
+        }
+        if let Some(id) = &self.operation_id {
+            op.insert("operationId".into(), id.as_str().into());
+        }
+        if !self.tags.is_empty() {
+            op.insert("tags".into(), self.tags.clone().into());
+        }
+
+        let mut parameters = path_params
+            .iter()
+            .filter(|name| !self.parameters.iter().any(|param| param["name"] == name.as_str() && param["in"] == "path"))
+            .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+            .collect::<Vec<_>>();
+        parameters.extend(self.parameters.iter().cloned());
+        if !parameters.is_empty() {
+            op.insert("parameters".into(), parameters.into());
+        }
+
+        if let Some(body) = &self.request_body {
+            op.insert("requestBody".into(), body.clone());
+        }
+
+        let responses = if self.responses.is_empty() {
+            json!({ "200": { "description": "OK" } })
+        } else {
+            Value::Object(self.responses.clone().into_iter().collect())
+        };
+        op.insert("responses".into(), responses);
+
+        Value::Object(op)
+    }
+}
+
+fn status_description(status: &str) -> String {
+    status
+        .parse::<u16>()
+        .ok()
+        .and_then(|status| hyper::StatusCode::from_u16(status).ok())
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Response")
+        .to_string()
+}
+
+/// Convert a route pattern like `/users/:id/:*rest` to an OpenAPI path template
+/// `/users/{id}/{rest}`, returning the names of the path parameters.
+fn path_template(pattern: &str) -> (String, Vec<String>) {
+    let mut params = Vec::new();
+    let path = pattern.split('/').map(|part| {
+        match part.strip_prefix(":*").or_else(|| part.strip_prefix(':')) {
+            Some(name) => {
+                params.push(name.to_string());
+                format!("{{{name}}}")
+            },
+            None => part.to_string(),
+        }
+    }).collect::<Vec<_>>().join("/");
+    (path, params)
+}
+
+/// An OpenAPI 3.1 document.
+///
+/// Created with [`PathRouter::openapi`](crate::server::PathRouter::openapi).
+#[derive(Debug, Default, Clone)]
+pub struct OpenApi {
+    info: Info,
+    paths: BTreeMap<String, BTreeMap<String, Value>>,
+    schemas: Schemas,
+}
+
+impl OpenApi {
+    pub fn new(info: Info) -> Self {
+        Self {
+            info,
+            ..Default::default()
+        }
+    }
+
+    /// Add an operation for a route pattern and method
+    pub fn add(&mut self, pattern: &str, method: &Method, operation: &Operation) {
+        let (path, params) = path_template(pattern);
+        self.schemas.extend(operation.schemas.clone());
+        self.paths
+            .entry(path)
+            .or_default()
+            .insert(method.as_str().to_ascii_lowercase(), operation.to_json(&params));
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut info = json!({ "title": self.info.title, "version": self.info.version });
+        if let Some(description) = &self.info.description {
+            info["description"] = description.as_str().into();
+        }
+
+        let mut doc = json!({
+            "openapi": "3.1.0",
+            "info": info,
+            "paths": self.paths,
+        });
+        if !self.schemas.is_empty() {
+            doc["components"] = json!({ "schemas": self.schemas });
+        }
+        doc
+    }
+
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap()
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        write_yaml(&mut out, &self.to_json(), 0);
+        out
+    }
+}
+
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        // JSON strings are valid YAML flow scalars
+        Value::String(s) => serde_json::to_string(s).unwrap(),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn write_yaml(out: &mut String, value: &Value, indent: usize) {
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => for (key, value) in map {
+            let key = serde_json::to_string(key).unwrap();
+            match value {
+                Value::Object(m) if !m.is_empty() => {
+                    let _ = writeln!(out, "{pad}{key}:");
+                    write_yaml(out, value, indent + 2);
+                },
+                Value::Array(a) if !a.is_empty() => {
+                    let _ = writeln!(out, "{pad}{key}:");
+                    write_yaml(out, value, indent);
+                },
+                scalar => { let _ = writeln!(out, "{pad}{key}: {}", yaml_scalar(scalar)); },
+            }
+        },
+        Value::Array(items) if !items.is_empty() => for item in items {
+            match item {
+                Value::Object(m) if !m.is_empty() => {
+                    let _ = writeln!(out, "{pad}-");
+                    write_yaml(out, item, indent + 2);
+                },
+                Value::Array(a) if !a.is_empty() => {
+                    let _ = writeln!(out, "{pad}-");
+                    write_yaml(out, item, indent + 2);
+                },
+                scalar => { let _ = writeln!(out, "{pad}- {}", yaml_scalar(scalar)); },
+            }
+        },
+        scalar => { let _ = writeln!(out, "{pad}{}", yaml_scalar(scalar)); },
+    }
+}
@@ -0,0 +1,391 @@
+use std::{borrow::Cow, future::Future, marker::PhantomData, sync::Arc};
+
+use hyper::{body::Bytes, HeaderMap, Method, StatusCode, Uri, Version};
+
+use crate::{
+    all_variants, all_variants_with_last,
+    extract::{
+        Capture, Caught, CookieJar, Extension, File, Flash, FromForm, Html, IntoResponseParts, Json, Lenient,
+        MatchedPath, Multipart, Parts, PrivateCookies, Problem, Query, Redirect, SignedCookies, State, TypedHeader,
+        UrlEncoded, APPLICATION_PROBLEM_JSON,
+    },
+    server::{router::RouteKind, Handler},
+    session::Session,
+    HttpError, Request, Response,
+};
+
+use super::{OpenApi, Operation, ToSchema};
+
+/// Describe how an extractor contributes to an operation.
+pub trait OperationInput {
+    fn operation_input(_op: &mut Operation) {}
+}
+
+/// Describe the responses produced by a handlers return type.
+pub trait OperationOutput {
+    fn operation_output(_op: &mut Operation) {}
+}
+
+impl<T: ToSchema> OperationInput for Query<T> {
+    fn operation_input(op: &mut Operation) {
+        op.parameters::<T>("query");
+    }
+}
+
+impl<T: ToSchema> OperationInput for Json<T> {
+    fn operation_input(op: &mut Operation) {
+        op.request_body::<T>(mime::APPLICATION_JSON.as_ref());
+    }
+}
+
+impl<T: ToSchema> OperationInput for UrlEncoded<T> {
+    fn operation_input(op: &mut Operation) {
+        op.request_body::<T>(mime::APPLICATION_WWW_FORM_URLENCODED.as_ref());
+    }
+}
+
+impl<T: ToSchema + FromForm> OperationInput for Multipart<T> {
+    fn operation_input(op: &mut Operation) {
+        op.request_body::<T>(mime::MULTIPART_FORM_DATA.as_ref());
+    }
+}
+
+impl OperationInput for String {
+    fn operation_input(op: &mut Operation) {
+        op.request_body::<String>(mime::TEXT_PLAIN_UTF_8.as_ref());
+    }
+}
+
+impl OperationInput for Bytes {
+    fn operation_input(op: &mut Operation) {
+        op.request_body::<Bytes>(mime::APPLICATION_OCTET_STREAM.as_ref());
+    }
+}
+
+impl<T: OperationInput> OperationInput for Option<T> {
+    fn operation_input(op: &mut Operation) {
+        let before = op.parameters.len();
+        T::operation_input(op);
+        // Anything added by an optional extractor isn't required
+        for param in op.parameters.iter_mut().skip(before) {
+            if param["in"] != "path" {
+                param["required"] = false.into();
+            }
+        }
+        if let Some(body) = op.request_body.as_mut() {
+            body["required"] = false.into();
+        }
+    }
+}
+
+impl<T: OperationInput> OperationInput for Lenient<T> {
+    fn operation_input(op: &mut Operation) {
+        T::operation_input(op)
+    }
+}
+
+impl<T: OperationInput, E> OperationInput for Result<T, E> {
+    fn operation_input(op: &mut Operation) {
+        T::operation_input(op)
+    }
+}
+
+impl<H: headers::Header> OperationInput for TypedHeader<H> {
+    fn operation_input(op: &mut Operation) {
+        let schema = op.schema::<String>();
+        op.parameter(H::name().as_str(), "header", true, schema);
+    }
+}
+
+// Path parameters come from the route pattern
+impl<T> OperationInput for Capture<T> {}
+impl<T> OperationInput for State<T> {}
+impl OperationInput for CookieJar {}
+impl OperationInput for SignedCookies {}
+impl OperationInput for PrivateCookies {}
+impl OperationInput for Session {}
+impl OperationInput for Flash {}
+impl OperationInput for MatchedPath {}
+impl OperationInput for Caught {}
+impl<T> OperationInput for Extension<T> {}
+impl OperationInput for Request {}
+impl OperationInput for Parts {}
+impl OperationInput for Method {}
+impl OperationInput for Uri {}
+impl OperationInput for Version {}
+impl OperationInput for HeaderMap {}
+
+macro_rules! impl_operation_output {
+    ($content: expr => $($ty: ty),* $(,)?) => {
+        $(
+            impl OperationOutput for $ty {
+                fn operation_output(op: &mut Operation) {
+                    op.response::<String>("200", $content.as_ref());
+                }
+            }
+        )*
+    };
+}
+
+impl_operation_output!(mime::TEXT_PLAIN_UTF_8 => String, &'static str, Cow<'static, str>);
+impl_operation_output!(mime::APPLICATION_OCTET_STREAM => Bytes, Vec<u8>, &'static [u8], File);
+
+impl OperationOutput for () {
+    fn operation_output(op: &mut Operation) {
+        op.empty_response("200");
+    }
+}
+
+impl OperationOutput for Response {}
+impl OperationOutput for HeaderMap {}
+impl OperationOutput for StatusCode {}
+
+impl OperationOutput for Redirect {
+    fn operation_output(op: &mut Operation) {
+        op.empty_response("3XX");
+    }
+}
+
+impl<T: OperationOutput, E: OperationOutput> OperationOutput for Result<T, E> {
+    fn operation_output(op: &mut Operation) {
+        T::operation_output(op);
+        E::operation_output(op);
+    }
+}
+
+// Errors can have any status, so they are documented as the default response. Their body is empty
+// unless the router renders one with `PathRouter::rejection_body`
+impl OperationOutput for HttpError {
+    fn operation_output(op: &mut Operation) {
+        op.empty_response("default");
+    }
+}
+
+impl OperationOutput for crate::Error {
+    fn operation_output(op: &mut Operation) {
+        op.empty_response("default");
+    }
+}
+
+impl OperationOutput for Problem {
+    fn operation_output(op: &mut Operation) {
+        op.response::<Problem>("default", APPLICATION_PROBLEM_JSON);
+    }
+}
+
+impl<T: ToSchema> OperationOutput for Json<T> {
+    fn operation_output(op: &mut Operation) {
+        op.response::<T>("200", mime::APPLICATION_JSON.as_ref());
+    }
+}
+
+impl<T: ToSchema> OperationOutput for UrlEncoded<T> {
+    fn operation_output(op: &mut Operation) {
+        op.response::<T>("200", mime::APPLICATION_WWW_FORM_URLENCODED.as_ref());
+    }
+}
+
+impl<T> OperationOutput for Html<T> {
+    fn operation_output(op: &mut Operation) {
+        op.response::<String>("200", mime::TEXT_HTML_UTF_8.as_ref());
+    }
+}
+
+impl<R: OperationOutput> OperationOutput for (StatusCode, R) {
+    fn operation_output(op: &mut Operation) {
+        R::operation_output(op);
+    }
+}
+
+macro_rules! impl_operation_output_parts {
+    ($($ty: ident),* $(,)?) => {
+        impl<R: OperationOutput, $($ty: IntoResponseParts,)*> OperationOutput for ($($ty,)* R,) {
+            fn operation_output(op: &mut Operation) {
+                R::operation_output(op);
+            }
+        }
+
+        impl<R: OperationOutput, $($ty: IntoResponseParts,)*> OperationOutput for (StatusCode, $($ty,)* R,) {
+            fn operation_output(op: &mut Operation) {
+                R::operation_output(op);
+            }
+        }
+    };
+}
+
+all_variants!(impl_operation_output_parts);
+
+/// A handler whose extractors and return type can be described in an OpenAPI document.
+pub trait OperationHandler<P>: Handler<P> {
+    fn operation() -> Operation;
+}
+
+impl<F, R, B> OperationHandler<((),)> for F
+where
+    F: Handler<((),)> + Fn() -> R,
+    R: Future<Output = B>,
+    B: OperationOutput,
+{
+    fn operation() -> Operation {
+        let mut op = Operation::default();
+        B::operation_output(&mut op);
+        op
+    }
+}
+
+macro_rules! impl_operation_handler {
+    (($($i: ident),* $(,)?), $last: ident $(,)?) => {
+        impl<F, R, B, M, X $(, $i)*, $last> OperationHandler<(X, M, $($i,)* $last,)> for F
+        where
+            F: Handler<(X, M, $($i,)* $last,)> + Fn($($i,)* $last) -> R,
+            R: Future<Output = B>,
+            B: OperationOutput,
+            $($i: OperationInput,)*
+            $last: OperationInput,
+        {
+            fn operation() -> Operation {
+                let mut op = Operation::default();
+                $($i::operation_input(&mut op);)*
+                $last::operation_input(&mut op);
+                B::operation_output(&mut op);
+                op
+            }
+        }
+    };
+}
+
+all_variants_with_last!(impl_operation_handler);
+
+/// A handler that is included in the routers OpenAPI document.
+///
+/// Created with [`doc`].
+pub struct Documented<H, P> {
+    handler: H,
+    operation: Arc<Operation>,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<H: Clone, P> Clone for Documented<H, P> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            operation: self.operation.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<H, P> std::fmt::Debug for Documented<H, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Documented")
+            .field("operation", &self.operation)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Include a handler in the routers OpenAPI document.
+///
+/// Parameters, the request body and the response are derived from the handlers signature, so the
+/// return type must be a concrete type instead of `impl IntoResponse`.
+pub fn doc<H, P>(handler: H) -> Documented<H, P>
+where
+    H: OperationHandler<P>,
+{
+    Documented {
+        handler,
+        operation: Arc::new(H::operation()),
+        _marker: PhantomData,
+    }
+}
+
+impl<H, P> Documented<H, P> {
+    fn operation_mut(&mut self) -> &mut Operation {
+        Arc::make_mut(&mut self.operation)
+    }
+
+    pub fn summary<S: ToString>(mut self, summary: S) -> Self {
+        self.operation_mut().summary = Some(summary.to_string());
+        self
+    }
+
+    pub fn description<S: ToString>(mut self, description: S) -> Self {
+        self.operation_mut().description = Some(description.to_string());
+        self
+    }
+
+    pub fn operation_id<S: ToString>(mut self, id: S) -> Self {
+        self.operation_mut().operation_id = Some(id.to_string());
+        self
+    }
+
+    pub fn tag<S: ToString>(mut self, tag: S) -> Self {
+        self.operation_mut().tags.push(tag.to_string());
+        self
+    }
+
+    /// Document an additional response, for example an error the handler can return.
+    pub fn response<T: ToSchema + ?Sized>(mut self, status: StatusCode, content_type: &str) -> Self {
+        self.operation_mut().response::<T>(status.as_str(), content_type);
+        self
+    }
+
+    /// Document an additional response without a body.
+    pub fn empty_response(mut self, status: StatusCode) -> Self {
+        self.operation_mut().empty_response(status.as_str());
+        self
+    }
+}
+
+impl<H, P> Handler<P> for Documented<H, P>
+where
+    H: Handler<P>,
+    P: 'static,
+{
+    type Future = H::Future;
+
+    fn call(self, req: Request) -> Self::Future {
+        self.handler.call(req)
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        self.handler.accepts(req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        self.handler.describe()
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        doc.add(path, method.unwrap_or(&Method::GET), &self.operation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation<H: OperationHandler<P>, P>(_: H) -> Operation {
+        H::operation()
+    }
+
+    #[test]
+    fn errors_are_the_default_response() {
+        async fn user() -> Result<Json<u32>, HttpError> {
+            Ok(Json(1))
+        }
+        async fn problem() -> Result<(), Problem> {
+            Ok(())
+        }
+
+        let op = operation(user);
+        assert!(op.responses["200"]["content"]["application/json"].is_object());
+        assert!(op.responses["default"].get("content").is_none());
+
+        let op = operation(problem);
+        assert_eq!(
+            op.responses["default"]["content"][APPLICATION_PROBLEM_JSON]["schema"]["$ref"],
+            "#/components/schemas/Problem"
+        );
+        assert!(op.schemas.contains_key("Problem"));
+    }
+}
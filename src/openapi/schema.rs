@@ -0,0 +1,260 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde_json::{json, Value};
+
+use crate::extract::{Problem, TempFile};
+
+/// Named schemas that are referenced from the document with `#/components/schemas/{name}`.
+pub type Schemas = BTreeMap<String, Value>;
+
+/// A type that can be described with a JSON schema in an OpenAPI document.
+///
+/// Can be derived with `#[derive(ToSchema)]`. Derived structs are added to the document components
+/// and referenced by name.
+pub trait ToSchema {
+    /// The schema for the type. Named types should be inserted into `schemas` and a `$ref` to them
+    /// returned instead.
+    fn schema(schemas: &mut Schemas) -> Value;
+
+    /// Whether a field of this type must be present.
+    fn required() -> bool {
+        true
+    }
+}
+
+/// Reference a named schema in the document components
+pub fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// Resolve a `$ref` schema to the schema it points to.
+pub fn resolve<'a>(schema: &'a Value, schemas: &'a Schemas) -> &'a Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix("#/components/schemas/")
+            .and_then(|name| schemas.get(name))
+            .unwrap_or(schema),
+        None => schema,
+    }
+}
+
+macro_rules! impl_to_schema {
+    ($schema: tt => $($ty: ty),* $(,)?) => {
+        $(
+            impl ToSchema for $ty {
+                fn schema(_: &mut Schemas) -> Value {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_schema!({ "type": "boolean" } => bool);
+impl_to_schema!({ "type": "integer", "format": "int32" } => i8, i16, i32);
+impl_to_schema!({ "type": "integer", "format": "int64" } => i64, i128, isize);
+impl_to_schema!({ "type": "integer", "format": "int32", "minimum": 0 } => u8, u16, u32);
+impl_to_schema!({ "type": "integer", "format": "int64", "minimum": 0 } => u64, u128, usize);
+impl_to_schema!({ "type": "number", "format": "float" } => f32);
+impl_to_schema!({ "type": "number", "format": "double" } => f64);
+impl_to_schema!({ "type": "string" } => String, str, char, Cow<'_, str>);
+impl_to_schema!({} => Value);
+impl_to_schema!({ "type": "string", "format": "binary" } => TempFile, hyper::body::Bytes);
+impl_to_schema!({ "type": "null" } => ());
+
+impl ToSchema for Problem {
+    fn schema(schemas: &mut Schemas) -> Value {
+        if __private::register(schemas, "Problem") {
+            __private::object(schemas, "Problem", Some("RFC 9457 problem details"), vec![
+                ("type", json!({ "type": "string", "format": "uri-reference" }), false, None),
+                ("title", json!({ "type": "string" }), false, None),
+                ("status", json!({ "type": "integer", "minimum": 100, "maximum": 599 }), true, None),
+                ("detail", json!({ "type": "string" }), false, None),
+                ("instance", json!({ "type": "string", "format": "uri-reference" }), false, None),
+            ]);
+        }
+        reference("Problem")
+    }
+}
+
+impl<T: ToSchema> ToSchema for Option<T> {
+    fn schema(schemas: &mut Schemas) -> Value {
+        T::schema(schemas)
+    }
+
+    fn required() -> bool {
+        false
+    }
+}
+
+macro_rules! impl_to_schema_array {
+    ($($ty: ident),* $(,)?) => {
+        $(
+            impl<T: ToSchema> ToSchema for $ty<T> {
+                fn schema(schemas: &mut Schemas) -> Value {
+                    json!({ "type": "array", "items": T::schema(schemas) })
+                }
+            }
+        )*
+    };
+}
+
+impl_to_schema_array!(Vec, HashSet, BTreeSet);
+
+impl<T: ToSchema> ToSchema for [T] {
+    fn schema(schemas: &mut Schemas) -> Value {
+        json!({ "type": "array", "items": T::schema(schemas) })
+    }
+}
+
+impl<T: ToSchema, const N: usize> ToSchema for [T; N] {
+    fn schema(schemas: &mut Schemas) -> Value {
+        json!({ "type": "array", "items": T::schema(schemas), "minItems": N, "maxItems": N })
+    }
+}
+
+macro_rules! impl_to_schema_map {
+    ($($ty: ident),* $(,)?) => {
+        $(
+            impl<K, V: ToSchema> ToSchema for $ty<K, V> {
+                fn schema(schemas: &mut Schemas) -> Value {
+                    json!({ "type": "object", "additionalProperties": V::schema(schemas) })
+                }
+            }
+        )*
+    };
+}
+
+impl_to_schema_map!(HashMap, BTreeMap);
+
+macro_rules! impl_to_schema_wrapper {
+    ($($ty: ident),* $(,)?) => {
+        $(
+            impl<T: ToSchema + ?Sized> ToSchema for $ty<T> {
+                fn schema(schemas: &mut Schemas) -> Value {
+                    T::schema(schemas)
+                }
+
+                fn required() -> bool {
+                    T::required()
+                }
+            }
+        )*
+    };
+}
+
+impl_to_schema_wrapper!(Box, Arc);
+
+impl<T: ToSchema + ?Sized> ToSchema for &T {
+    fn schema(schemas: &mut Schemas) -> Value {
+        T::schema(schemas)
+    }
+
+    fn required() -> bool {
+        T::required()
+    }
+}
+
+/// Helpers used by `#[derive(ToSchema)]`
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::Value;
+    use serde_json::{json, Map};
+
+    use super::{reference, Schemas};
+
+    /// Name of a generic schema, like `Page_User` for `Page<User>`, so every instantiation gets
+    /// it's own component. The parameters are given as their `std::any::type_name`.
+    pub fn generic_name(name: &str, params: &[&str]) -> String {
+        let mut name = name.to_string();
+        for param in params {
+            name.push('_');
+            name.push_str(&short_type_name(param));
+        }
+        name
+    }
+
+    /// Keep the last segment of every path, `alloc::vec::Vec<app::User>` becomes `Vec_User`
+    fn short_type_name(type_name: &str) -> String {
+        let mut segments = Vec::new();
+        let mut segment = String::new();
+        for c in type_name.chars() {
+            match c {
+                c if c.is_alphanumeric() || c == '_' => segment.push(c),
+                ':' => segment.clear(),
+                _ if !segment.is_empty() => segments.push(std::mem::take(&mut segment)),
+                _ => {},
+            }
+        }
+        if !segment.is_empty() {
+            segments.push(segment);
+        }
+        segments.join("_")
+    }
+
+    /// Reserve a name in the components, returns `false` if the schema is already registered so
+    /// recursive types don't recurse forever.
+    pub fn register(schemas: &mut Schemas, name: &str) -> bool {
+        if schemas.contains_key(name) {
+            return false;
+        }
+        schemas.insert(name.to_string(), Value::Null);
+        true
+    }
+
+    pub fn object(
+        schemas: &mut Schemas,
+        name: &str,
+        description: Option<&str>,
+        fields: Vec<(&str, Value, bool, Option<&str>)>,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for (field, mut schema, is_required, description) in fields {
+            if let (Some(description), Some(schema)) = (description, schema.as_object_mut()) {
+                schema.insert("description".into(), description.into());
+            }
+            if is_required {
+                required.push(field);
+            }
+            properties.insert(field.to_string(), schema);
+        }
+
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            schema["required"] = required.into();
+        }
+        if let Some(description) = description {
+            schema["description"] = description.into();
+        }
+        schemas.insert(name.to_string(), schema);
+        reference(name)
+    }
+
+    pub fn string_enum(schemas: &mut Schemas, name: &str, description: Option<&str>, variants: &[&str]) -> Value {
+        let mut schema = json!({ "type": "string", "enum": variants });
+        if let Some(description) = description {
+            schema["description"] = description.into();
+        }
+        schemas.insert(name.to_string(), schema);
+        reference(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::__private::generic_name;
+
+    #[test]
+    fn generic_names() {
+        assert_eq!(generic_name("Plain", &[]), "Plain");
+        assert_eq!(generic_name("Page", &["app::User"]), "Page_User");
+        assert_eq!(generic_name("Page", &["alloc::vec::Vec<alloc::string::String>"]), "Page_Vec_String");
+        assert_eq!(generic_name("Pair", &["app::User", "core::option::Option<app::Post>"]), "Pair_User_Option_Post");
+        assert_eq!(generic_name("Page", &["&str"]), "Page_str");
+    }
+}
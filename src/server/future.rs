@@ -50,11 +50,9 @@ macro_rules! opaque_future {
     };
 }
 
+type IntoOk = fn(Response<Body>) -> Result<Response<Body>, Infallible>;
+
 opaque_future! {
      /// The response future for [`IntoService`](super::IntoService).
-    pub type IntoServiceFuture<F> =
-        Map<
-            F,
-            fn(Response<Body>) -> Result<Response<Body>, Infallible>,
-        >;
+    pub type IntoServiceFuture<F> = Map<F, IntoOk>;
 }
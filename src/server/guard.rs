@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use hyper::header::{self, HeaderName, HeaderValue};
+use mime::Mime;
+
+use crate::{extract::headers::{self, HeaderMapExt}, Request};
+
+/// A predicate that decides if a route is allowed to handle a request.
+///
+/// Guards are attached to handlers with [`Handler::guard`](super::Handler::guard). When a guard
+/// rejects a request the router moves on to the next matching route, the same way it would if
+/// the path had not matched at all.
+pub trait Guard: Send + Sync + 'static {
+    fn check(&self, req: &Request) -> bool;
+
+    /// Only pass if both guards pass
+    fn and<G: Guard>(self, other: G) -> And<Self, G>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Pass if either guard passes
+    fn or<G: Guard>(self, other: G) -> Or<Self, G>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, req: &Request) -> bool {
+        self(req)
+    }
+}
+
+impl<G: Guard + ?Sized> Guard for Arc<G> {
+    fn check(&self, req: &Request) -> bool {
+        (**self).check(req)
+    }
+}
+
+impl Guard for Box<dyn Guard> {
+    fn check(&self, req: &Request) -> bool {
+        (**self).check(req)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+impl<A: Guard, B: Guard> Guard for And<A, B> {
+    fn check(&self, req: &Request) -> bool {
+        self.0.check(req) && self.1.check(req)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(A, B);
+impl<A: Guard, B: Guard> Guard for Or<A, B> {
+    fn check(&self, req: &Request) -> bool {
+        self.0.check(req) || self.1.check(req)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Not<G>(G);
+impl<G: Guard> Guard for Not<G> {
+    fn check(&self, req: &Request) -> bool {
+        !self.0.check(req)
+    }
+}
+
+/// Invert the result of a guard
+pub fn not<G: Guard>(guard: G) -> Not<G> {
+    Not(guard)
+}
+
+#[derive(Debug, Clone)]
+pub struct Accept(Mime);
+
+impl Guard for Accept {
+    fn check(&self, req: &Request) -> bool {
+        // No preference from the client means anything is acceptable
+        match req.headers().typed_get::<headers::Accept>() {
+            Some(accept) if accept.iter().next().is_some() => accept.explicitly_accepts(&self.0),
+            _ => true,
+        }
+    }
+}
+
+/// Pass if the `Accept` header of the request explicitly accepts the media type, see
+/// [`Accept::explicitly_accepts`](headers::Accept::explicitly_accepts). A blanket `*/*` only counts
+/// when the client didn't ask for anything more specific, otherwise a browser would be routed to
+/// every handler that accepts some media type.
+///
+/// Requests without a valid `Accept` header, or with only `*/*`, will always pass.
+///
+/// # Panics
+///
+/// If the media type isn't in the form `type/subtype`
+pub fn accept<S: AsRef<str>>(media: S) -> Accept {
+    Accept(essence(media.as_ref()).expect("Invalid media type for accept guard"))
+}
+
+/// The media type without parameters
+fn essence(media: &str) -> Option<Mime> {
+    media.parse::<Mime>().ok()?.essence_str().parse().ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentType(Mime);
+
+impl Guard for ContentType {
+    fn check(&self, req: &Request) -> bool {
+        req.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(essence)
+            .is_some_and(|mime| {
+                mime.type_() == self.0.type_() && (self.0.subtype() == mime::STAR || mime.subtype() == self.0.subtype())
+            })
+    }
+}
+
+/// Pass if the `Content-Type` of the request matches the media type. Parameters like `charset`
+/// and `boundary` are ignored.
+///
+/// # Panics
+///
+/// If the media type isn't in the form `type/subtype`
+pub fn content_type<S: AsRef<str>>(media: S) -> ContentType {
+    ContentType(essence(media.as_ref()).expect("Invalid media type for content type guard"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Header {
+    name: HeaderName,
+    value: Option<HeaderValue>,
+}
+
+impl Guard for Header {
+    fn check(&self, req: &Request) -> bool {
+        let mut values = req.headers().get_all(&self.name).iter();
+        match &self.value {
+            Some(expected) => values.any(|v| v == expected),
+            None => values.next().is_some(),
+        }
+    }
+}
+
+/// Pass if the request has a header with the given value.
+///
+/// # Panics
+///
+/// If the name or value are not valid header names or values
+pub fn header<K, V>(name: K, value: V) -> Header
+where
+    K: TryInto<HeaderName>,
+    K::Error: std::fmt::Debug,
+    V: TryInto<HeaderValue>,
+    V::Error: std::fmt::Debug,
+{
+    Header {
+        name: name.try_into().expect("Invalid header name for header guard"),
+        value: Some(value.try_into().expect("Invalid header value for header guard")),
+    }
+}
+
+/// Pass if the request has the header regardless of it's value.
+///
+/// # Panics
+///
+/// If the name is not a valid header name
+pub fn has_header<K>(name: K) -> Header
+where
+    K: TryInto<HeaderName>,
+    K::Error: std::fmt::Debug,
+{
+    Header {
+        name: name.try_into().expect("Invalid header name for header guard"),
+        value: None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryHas(String);
+
+impl Guard for QueryHas {
+    fn check(&self, req: &Request) -> bool {
+        req.uri()
+            .query()
+            .map(|query| {
+                query
+                    .split('&')
+                    .map(|pair| pair.split_once('=').map(|(k, _)| k).unwrap_or(pair))
+                    .map(|key| key.replace('+', " "))
+                    .any(|key| {
+                        percent_encoding::percent_decode_str(key.as_str())
+                            .decode_utf8()
+                            .map(|key| key == self.0)
+                            .unwrap_or(false)
+                    })
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Pass if the query string contains the key, with or without a value.
+pub fn query_has<S: ToString>(key: S) -> QueryHas {
+    QueryHas(key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+
+    use super::*;
+    use crate::{server::{methods, Handler, PathRouter}, Body};
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> Request {
+        let mut request = Request::builder().uri(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    async fn body(router: &PathRouter, request: Request) -> String {
+        let response = Handler::call(router.clone(), request).await;
+        String::from_utf8(response.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap()
+    }
+
+    async fn json() -> &'static str {
+        "json"
+    }
+
+    async fn html() -> &'static str {
+        "html"
+    }
+
+    #[tokio::test]
+    async fn rejected_routes_fall_through() {
+        let router = PathRouter::default()
+            .route("/items", methods::get(json.guard(accept("application/json"))))
+            .route("/items", methods::get(html));
+
+        assert_eq!(body(&router, request("/items", &[("accept", "application/json")])).await, "json");
+        assert_eq!(body(&router, request("/items", &[("accept", "text/html")])).await, "html");
+        // The blanket `*/*` of a browser doesn't count next to the specific media types
+        let browser = ("accept", "text/html,application/xhtml+xml,*/*;q=0.8");
+        assert_eq!(body(&router, request("/items", &[browser])).await, "html");
+        assert_eq!(body(&router, request("/items", &[])).await, "json");
+    }
+
+    #[tokio::test]
+    async fn guards_are_checked_in_rank_order() {
+        let router = PathRouter::default()
+            .route("/users/:id", methods::get(html))
+            .route("/users/me", methods::get(json.guard(has_header("authorization"))));
+
+        assert_eq!(body(&router, request("/users/me", &[("authorization", "Bearer x")])).await, "json");
+        assert_eq!(body(&router, request("/users/me", &[])).await, "html");
+    }
+
+    #[test]
+    fn combinators() {
+        let json = content_type("application/json");
+        let versioned = header("x-version", "2");
+        let req = request("/?debug", &[("content-type", "application/json; charset=utf-8"), ("x-version", "2")]);
+        let other = request("/", &[("content-type", "text/plain")]);
+
+        assert!(json.clone().and(versioned.clone()).check(&req));
+        assert!(!json.clone().and(versioned.clone()).check(&other));
+        assert!(json.clone().or(query_has("debug")).check(&req));
+        assert!(!json.clone().or(query_has("debug")).check(&other));
+        assert!(not(json.clone()).check(&other));
+        assert!(!not(json).check(&req));
+    }
+
+    #[test]
+    fn accept_ranges() {
+        let guard = accept("text/*");
+        assert!(guard.check(&request("/", &[("accept", "text/html")])));
+        assert!(!guard.check(&request("/", &[("accept", "application/json, */*")])));
+        assert!(accept("text/html").check(&request("/", &[("accept", "*/*")])));
+        assert!(!accept("text/html").check(&request("/", &[("accept", "text/*, text/html;q=0")])));
+    }
+}
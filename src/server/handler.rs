@@ -3,21 +3,46 @@ use std::{
     future::Future,
     marker::PhantomData,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
-use hyper::{body::{Body as HttpBody, Bytes}, header::{self, HeaderValue}};
+use hyper::{body::{Body as HttpBody, Bytes}, Method};
 use crate::{all_variants_with_last, Body, BoxError, Request, Response};
-use crate::extract::{CookieJar, IntoResponse, FromRequest, FromParts};
+use crate::extract::{CookieJar, IntoResponse, IntoResponseParts, FromRequest, FromParts};
 use tower::{Layer, Service, ServiceExt};
 
 use super::future;
+use super::guard::Guard;
+use super::router::{Describe, RouteKind};
+use crate::openapi::OpenApi;
 
-pub trait Handler<P>: Clone + Sized + Send + 'static {
+/// An async function, or another type, that responds to requests.
+///
+/// Handlers are compiled into routes once, when they are registered, and those routes are shared
+/// by reference between the threads serving requests. That is why handlers, and everything they
+/// capture, have to be `Sync`; wrap captured values that aren't in a `Mutex`. Services that aren't
+/// `Sync` can be mounted with [`PathRouter::route_service`](super::PathRouter::route_service).
+pub trait Handler<P>: Clone + Sized + Send + Sync + 'static {
     type Future: Future<Output = Response> + Send + 'static;
 
     fn call(self, req: Request) -> Self::Future;
 
+    /// Whether the handler is willing to handle the request. Routers will skip handlers that
+    /// don't accept a request and try the next matching route.
+    fn accepts(&self, _req: &Request) -> bool {
+        true
+    }
+
+    /// Describe what the handler serves for route listings.
+    fn describe(&self) -> RouteKind {
+        RouteKind::Handler
+    }
+
+    /// Add the operations served by the handler to an OpenAPI document. The method is `None`
+    /// when the handler isn't registered for a specific method.
+    fn document(&self, _path: &str, _method: Option<&Method>, _doc: &mut OpenApi) {}
+
     fn into_service(self) -> HandlerService<Self, P> {
         HandlerService::new(self)
     }
@@ -33,6 +58,14 @@ pub trait Handler<P>: Clone + Sized + Send + 'static {
             _marker: PhantomData,
         }
     }
+
+    fn guard<G: Guard>(self, guard: G) -> Guarded<Self, P> {
+        Guarded {
+            guard: Arc::new(guard),
+            handler: self,
+            _marker: PhantomData,
+        }
+    }
 }
 
 pub struct HandlerService<H, D> {
@@ -58,6 +91,12 @@ impl<H, D> HandlerService<H, D> {
     }
 }
 
+impl<H: Handler<D>, D> Describe for HandlerService<H, D> {
+    fn describe(&self) -> RouteKind {
+        self.handler.describe()
+    }
+}
+
 impl<B, H, D> Service<Request<B>> for HandlerService<H, D>
 where
     H: Handler<D> + Clone + Send + 'static,
@@ -86,7 +125,7 @@ where
 pub struct Layered<L, H, D> {
     layer: L,
     handler: H,
-    _marker: PhantomData<D>,
+    _marker: PhantomData<fn() -> D>,
 }
 
 impl<L, H, D> std::fmt::Debug for Layered<L, H, D>
@@ -116,7 +155,7 @@ where
 
 impl<L, H, D> Handler<D> for Layered<L, H, D>
 where
-    L: Layer<HandlerService<H, D>> + Clone + Send + 'static,
+    L: Layer<HandlerService<H, D>> + Clone + Send + Sync + 'static,
     H: Handler<D>,
     L::Service: Service<Request, Error = Infallible> + Clone + Send + 'static,
     <L::Service as Service<Request>>::Response: IntoResponse,
@@ -134,11 +173,69 @@ where
             value.into_response()
         })
     }
+
+    fn accepts(&self, req: &Request) -> bool {
+        self.handler.accepts(req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        self.handler.describe()
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        self.handler.document(path, method, doc)
+    }
+}
+
+pub struct Guarded<H, D> {
+    guard: Arc<dyn Guard>,
+    handler: H,
+    _marker: PhantomData<fn() -> D>,
+}
+
+impl<H, D> std::fmt::Debug for Guarded<H, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Guarded").finish_non_exhaustive()
+    }
+}
+
+impl<H: Clone, D> Clone for Guarded<H, D> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+            handler: self.handler.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<H, D> Handler<D> for Guarded<H, D>
+where
+    H: Handler<D>,
+    D: 'static,
+{
+    type Future = H::Future;
+
+    fn call(self, req: Request) -> Self::Future {
+        self.handler.call(req)
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        self.guard.check(req) && self.handler.accepts(req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        self.handler.describe()
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        self.handler.document(path, method, doc)
+    }
 }
 
 impl<F, R, B> Handler<((),)> for F
 where
-    F: Fn() -> R + Clone + Send + 'static,
+    F: Fn() -> R + Clone + Send + Sync + 'static,
     R: Future<Output = B> + Send + 'static,
     B: IntoResponse,
     Self: Sized,
@@ -167,39 +264,28 @@ macro_rules! impl_handler {
             fn call(self, req: Request) -> Self::Future {
                 let handler = self.clone();
                 Box::pin(async move {
-                    let cookies = CookieJar::default();
                     let (parts, body) = req.into_parts();
+                    // Share the jar of a middleware so it sees the changes of the handler
+                    let cookies = parts.extensions.get::<CookieJar>().cloned().unwrap_or_default();
 
                     paste::paste! {
                         $(let [<_i_$i:lower>] = match $i::from_parts(&parts, cookies.clone()).await {
                             Ok(v) => v,
-                            Err(e) => {
-                                log::error!("Failed to parse handler parameter: {}", e);
-                                return e.into_response()
-                            },
+                            Err(e) => return with_cookies(&cookies, e.into_response()),
                         };)*
 
                         let [<_last_$last:lower>] = match $last::from_request(Request::from_parts(parts, body), cookies.clone()).await {
                             Ok(v) => v,
-                            Err(e) => {
-                                log::error!("Failed to parse handler parameter: {}", e);
-                                return e.into_response()
-                            },
+                            Err(e) => return with_cookies(&cookies, e.into_response()),
                         };
 
-                        let mut response = handler(
+                        let response = handler(
                             $([<_i_$i:lower>],)*
                             [<_last_$last:lower>],
                         ).await.into_response();
                     }
 
-                    let jar = cookies.as_ref();
-                    if jar.delta().count() > 0 {
-                        let headers = response.headers_mut();
-                        let cookies = jar.delta().map(|v| v.stripped().encoded().to_string()).collect::<Vec<_>>().join(";");
-                        headers.insert(header::SET_COOKIE, HeaderValue::from_str(cookies.as_str()).unwrap());
-                    }
-                    response
+                    with_cookies(&cookies, response)
                 })
             }
         }
@@ -207,3 +293,9 @@ macro_rules! impl_handler {
 }
 
 all_variants_with_last!(impl_handler);
+
+/// Send the cookie changes of the extractors, also when one of them rejected the request
+fn with_cookies(cookies: &CookieJar, response: Response) -> Response {
+    let Ok(response) = cookies.clone().into_response_parts(response);
+    response
+}
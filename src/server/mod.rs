@@ -12,11 +12,14 @@ use tokio::net::TcpListener;
 use tower::Service;
 
 pub mod router;
+pub mod guard;
 pub(crate) mod future;
 pub(crate) mod handler;
 
-pub use handler::Handler;
-pub use router::{PathRouter, FileRouter, methods, TemplateRouter, TemplateEngine, RenderError};
+pub use handler::{Handler, Guarded};
+pub use wayfinder_macros::{get, post, put, delete, patch, head, options, trace, connect};
+pub use router::{PathRouter, DynamicRouter, handle_error, FileRouter, methods, MethodFilter, StatusClass, TemplateRouter, TemplateEngine, RenderError, Describe, RouteTable, Mount};
+use router::{RouteInfo, RouteKind};
 
 use crate::{Body, Request, Response, Result};
 
@@ -31,13 +34,15 @@ where
 {
     address: SocketAddr,
     router: R,
+    routes: Option<RouteTable>,
 }
 
 impl Server<FileRouter> {
     pub fn bind<I: Into<IpAddr>>(address: I, port: u16) -> Self {
         Self {
             address: SocketAddr::new(address.into(), port),
-            router: FileRouter::new("pages", false),
+            router: FileRouter::new("pages"),
+            routes: None,
         }
     }
 }
@@ -55,9 +60,25 @@ where
         Server {
             address: self.address,
             router,
+            routes: None,
         } 
     }
 
+    /// Log the route table of the router when the server starts.
+    pub fn log_routes(mut self) -> Self
+    where
+        R: Describe,
+    {
+        self.routes = Some(match self.router.describe() {
+            RouteKind::Router(table) => table,
+            kind => RouteTable {
+                routes: vec![RouteInfo { pattern: "/:*_".to_string(), kind }],
+                fallback: None,
+            },
+        });
+        self
+    }
+
     pub fn run(self) -> Result<()> {
         tokio::runtime::Builder::new_multi_thread()
             .worker_threads(4)
@@ -66,6 +87,9 @@ where
             .block_on(async move {
                 let listener = TcpListener::bind(self.address).await?;
                 log::info!("Listening to \x1b[33m{}\x1b[39m", self.address);
+                if let Some(routes) = self.routes.as_ref() {
+                    log::info!("Routes:\n{routes}");
+                }
 
                 let router = TowerToHyperService::new(self.router
                     .map_request(|req: Request<Incoming>| req.map(Body::new)));
@@ -0,0 +1,149 @@
+use std::ops::{Range, RangeInclusive};
+
+use hyper::{body::Body as _, header, StatusCode};
+
+use crate::{extract::{Caught, ResponseError}, Body, Request, Response};
+use hyper::http::request::Parts;
+
+use super::BoxedRoute;
+
+/// A status code, or range of status codes, that a catcher handles.
+pub trait IntoStatusRange {
+    fn into_status_range(self) -> RangeInclusive<u16>;
+}
+
+impl IntoStatusRange for u16 {
+    fn into_status_range(self) -> RangeInclusive<u16> {
+        self..=self
+    }
+}
+
+impl IntoStatusRange for StatusCode {
+    fn into_status_range(self) -> RangeInclusive<u16> {
+        self.as_u16().into_status_range()
+    }
+}
+
+impl IntoStatusRange for RangeInclusive<u16> {
+    fn into_status_range(self) -> RangeInclusive<u16> {
+        self
+    }
+}
+
+impl IntoStatusRange for Range<u16> {
+    fn into_status_range(self) -> RangeInclusive<u16> {
+        self.start..=self.end.saturating_sub(1)
+    }
+}
+
+/// A whole class of status codes, `StatusClass::ServerError` is every `5xx` status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusClass {
+    Informational,
+    Success,
+    Redirection,
+    ClientError,
+    ServerError,
+}
+
+impl IntoStatusRange for StatusClass {
+    fn into_status_range(self) -> RangeInclusive<u16> {
+        match self {
+            StatusClass::Informational => 100..=199,
+            StatusClass::Success => 200..=299,
+            StatusClass::Redirection => 300..=399,
+            StatusClass::ClientError => 400..=499,
+            StatusClass::ServerError => 500..=599,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Catcher {
+    range: RangeInclusive<u16>,
+    handler: BoxedRoute,
+}
+
+impl Catcher {
+    pub(crate) fn new(range: RangeInclusive<u16>, handler: BoxedRoute) -> Self {
+        Self { range, handler }
+    }
+}
+
+/// Find the catcher with the narrowest range that handles the status. Catchers registered first
+/// win when the ranges are the same size.
+pub(crate) fn find(catchers: &[Catcher], status: StatusCode) -> Option<&Catcher> {
+    catchers
+        .iter()
+        .filter(|catcher| catcher.range.contains(&status.as_u16()))
+        .min_by_key(|catcher| catcher.range.end().saturating_sub(*catcher.range.start()))
+}
+
+/// Replace an empty bodied error response with the response from a matching catcher.
+pub(crate) async fn catch(catchers: &[Catcher], parts: Parts, response: Response) -> Response {
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) || response.body().size_hint().exact() != Some(0) {
+        return response;
+    }
+
+    let catcher = match find(catchers, status) {
+        Some(catcher) => &catcher.handler,
+        None => return response,
+    };
+
+    let (original, _) = response.into_parts();
+    let mut req = Request::from_parts(parts, Body::empty());
+    req.extensions_mut().insert(Caught {
+        status,
+        headers: original.headers.clone(),
+        error: original.extensions.get::<ResponseError>().map(|error| error.0.clone()),
+    });
+
+    let mut caught = catcher.call(req).await.unwrap();
+
+    // Keep the original status unless the catcher explicitly changed it to another error
+    if caught.status().is_success() {
+        *caught.status_mut() = status;
+    }
+
+    // Headers like `Allow` and `WWW-Authenticate` still apply to the new response. Every value is
+    // kept, so one `Set-Cookie` header per cookie isn't reduced to the first
+    for name in original.headers.keys() {
+        if name == header::CONTENT_LENGTH || name == header::CONTENT_TYPE || caught.headers().contains_key(name) {
+            continue;
+        }
+        for value in original.headers.get_all(name) {
+            caught.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+
+    caught
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn not_found_page() -> &'static str {
+        "not found"
+    }
+
+    #[tokio::test]
+    async fn every_original_header_value_is_kept() {
+        let catchers = [Catcher::new(404u16.into_status_range(), BoxedRoute::new(not_found_page))];
+        let response = hyper::Response::builder()
+            .status(404)
+            .header(header::SET_COOKIE, "a=1")
+            .header(header::SET_COOKIE, "b=2")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let (parts, _) = Request::new(Body::empty()).into_parts();
+
+        let caught = catch(&catchers, parts, response).await;
+        assert_eq!(caught.status(), StatusCode::NOT_FOUND);
+        let cookies: Vec<_> = caught.headers().get_all(header::SET_COOKIE).iter().collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+        assert_eq!(caught.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arc_swap::ArcSwap;
+use http_body::Body as HttpBody;
+use hyper::{body::Bytes, Method};
+use tower::Service;
+
+use crate::{openapi::OpenApi, Body, BoxError, Request, Response};
+
+use super::{Describe, Handler, PathRouter, RouteKind};
+
+/// A handle to a [`PathRouter`] that can be changed while the server is running.
+///
+/// Every clone of the handle shares the same routes, so one clone can be given to the
+/// [`Server`](crate::server::Server) while another adds, removes or replaces routes. Changes are
+/// made to a copy of the router which is then swapped in atomically. Requests that already started
+/// keep using the version of the router they matched against.
+///
+/// ```ignore
+/// let router = DynamicRouter::new(PathRouter::default().route("/", index));
+///
+/// Server::bind(LOCAL, 3000)
+///     .with_router(router.clone())
+///     .run()?;
+///
+/// // Somewhere else, for example when a plugin is loaded
+/// router.route("/plugin", plugin);
+/// router.remove("/plugin");
+/// ```
+#[derive(Clone, Default)]
+pub struct DynamicRouter {
+    inner: Arc<ArcSwap<PathRouter>>,
+}
+
+impl std::fmt::Debug for DynamicRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicRouter").finish_non_exhaustive()
+    }
+}
+
+impl DynamicRouter {
+    pub fn new(router: PathRouter) -> Self {
+        Self {
+            inner: Arc::new(ArcSwap::from_pointee(router)),
+        }
+    }
+
+    /// The current version of the router
+    pub fn load(&self) -> Arc<PathRouter> {
+        self.inner.load_full()
+    }
+
+    /// Replace the whole router, returning the previous version.
+    pub fn replace(&self, router: PathRouter) -> Arc<PathRouter> {
+        self.inner.swap(Arc::new(router))
+    }
+
+    /// Change the router with a builder function.
+    ///
+    /// The function is given a copy of the current router. It may be called more than once if
+    /// another update happens at the same time, so it shouldn't have side effects.
+    pub fn update<F>(&self, update: F)
+    where
+        F: Fn(PathRouter) -> PathRouter,
+    {
+        self.inner.rcu(|current| update(PathRouter::clone(current)));
+    }
+
+    /// Add a route, or a nested router, to the current router.
+    pub fn route<S, H, D>(&self, path: S, route: H)
+    where
+        S: AsRef<str>,
+        H: Handler<D> + Send + 'static,
+        D: 'static,
+    {
+        self.update(|router| router.route(path.as_ref(), route.clone()));
+    }
+
+    /// Remove every route registered with the pattern, returns whether any route was removed.
+    pub fn remove(&self, pattern: &str) -> bool {
+        let mut removed = false;
+        self.inner.rcu(|current| {
+            let mut router = PathRouter::clone(current);
+            removed = router.remove(pattern);
+            router
+        });
+        removed
+    }
+}
+
+impl Describe for DynamicRouter {
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self.load().as_ref())
+    }
+}
+
+impl Handler<DynamicRouter> for DynamicRouter {
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, req: Request) -> Self::Future {
+        let router = PathRouter::clone(&self.load());
+        Handler::call(router, req)
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        self.load().accepts(req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        self.load().document(path, method, doc)
+    }
+}
+
+impl<B> Service<Request<B>> for DynamicRouter
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let future = Handler::call(self.clone(), req.map(Body::new));
+        Box::pin(async move { Ok(future.await) })
+    }
+}
@@ -12,16 +12,9 @@ use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::server::Handler;
+use super::{Describe, RouteKind};
 use crate::{BoxError, Body, Request, Response};
 
-#[derive(Debug, Clone)]
-pub struct RouterFlags(u8);
-bitflags::bitflags! {
-    impl RouterFlags: u8 {
-        const EnforceSlash = 1;
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct FileRouter {
     path: PathBuf,
@@ -100,6 +93,16 @@ impl Handler<FileRouter> for FileRouter {
                 .unwrap()
         })
     }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+}
+
+impl Describe for FileRouter {
+    fn describe(&self) -> RouteKind {
+        RouteKind::Named("FileRouter")
+    }
 }
 
 impl<B> Service<Request<B>> for FileRouter
@@ -1,5 +1,5 @@
 use std::{
-    convert::Infallible, future::Future, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}
+    any::Any, borrow::Cow, convert::Infallible, future::Future, pin::Pin, sync::Arc, task::{Context, Poll}
 };
 
 use http_body::Body as HttpBody;
@@ -9,21 +9,30 @@ use hyper::{
     HeaderMap, Method,
 };
 use pin_project_lite::pin_project;
-use regex::Regex;
-use tower::{
-    util::{BoxCloneService, Oneshot},
-    Service, ServiceExt,
-};
+use regex::{Captures, Regex};
+use tower::{Layer, Service, ServiceExt};
 use hyper::http::Extensions;
 
-use crate::{extract::UriParams, PercentDecodedStr};
+use crate::{extract::{render_rejection, BodyLimit, FromRef, Html, Json, MatchedPath, Redirect, RejectionBody, RouterState, Shared, UriParams}, PercentDecodedStr};
+use crate::openapi::{self, Info, OpenApi};
 
 use crate::{BoxError, Body, Request, Response, extract::IntoResponse};
 pub use super::Handler;
 
 mod file;
 mod template;
+mod normalize;
+mod table;
+mod catcher;
+mod dynamic;
+mod service;
 pub use file::FileRouter;
+pub use catcher::{IntoStatusRange, StatusClass};
+pub use dynamic::DynamicRouter;
+pub use service::{handle_error, HandleError, ServiceRoute};
+use catcher::Catcher;
+pub use table::{Describe, RouteInfo, RouteKind, RouteTable};
+pub use normalize::{RouterFlags, TrailingSlash};
 pub use template::{TemplateRouter, TemplateEngine, RenderError};
 
 lazy_static::lazy_static! {
@@ -31,62 +40,92 @@ lazy_static::lazy_static! {
     static ref CAPTURE: Regex = Regex::new(":([a-zA-Z_][a-zA-Z_\\d]*)").unwrap();
 }
 
+/// A type erased handler that is shared by every request.
+pub trait ErasedHandler: Send + Sync {
+    fn call(&self, request: Request) -> RouteFuture;
+    fn accepts(&self, request: &Request) -> bool;
+    fn describe(&self) -> RouteKind;
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi);
+}
+
 pub struct MakeErasedHandler<H> {
     pub handler: H,
-    pub into_route: fn(H) -> Route,
+    pub call: fn(&H, Request) -> RouteFuture,
+    pub accepts: fn(&H, &Request) -> bool,
+    pub describe: fn(&H) -> RouteKind,
+    pub document: fn(&H, &str, Option<&Method>, &mut OpenApi),
 }
 
-impl<H: Clone> Clone for MakeErasedHandler<H> {
-    fn clone(&self) -> Self {
-        Self {
-            handler: self.handler.clone(),
-            into_route: self.into_route,
-        }
+impl<H> ErasedHandler for MakeErasedHandler<H>
+where
+    H: Send + Sync + 'static,
+{
+    fn call(&self, req: Request) -> RouteFuture {
+        (self.call)(&self.handler, req)
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        (self.accepts)(&self.handler, req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        (self.describe)(&self.handler)
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        (self.document)(&self.handler, path, method, doc)
     }
 }
 
-pub struct Route<E = Infallible>(Mutex<BoxCloneService<Request, Response, E>>);
-impl Route {
-    pub(crate) fn new<T>(svc: T) -> Self
-    where
-        T: Service<Request, Error = Infallible> + Clone + Send + 'static,
-        T::Response: IntoResponse + 'static,
-        T::Future: Send + 'static,
-    {
-        Self(Mutex::new(BoxCloneService::new(
-            svc.map_future(|f| async move {
-                let result = f.await.unwrap();
-                Ok(result.into_response())
-            })
-        )))
+/// A handler wrapped in a layer. The layer is applied once when the route is built and the
+/// resulting service is shared by every request.
+pub struct MakeErasedLayered<S> {
+    inner: BoxedRoute,
+    service: S,
+}
+
+impl<S> ErasedHandler for MakeErasedLayered<S>
+where
+    S: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+    S::Response: IntoResponse + 'static,
+    S::Future: Send + 'static,
+{
+    fn call(&self, req: Request) -> RouteFuture {
+        let service = self.service.clone();
+        RouteFuture::from_future(async move {
+            match service.oneshot(req).await {
+                Ok(response) => response.into_response(),
+                Err(never) => match never {},
+            }
+        })
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        self.inner.accepts(req)
     }
 
-    pub(crate) fn oneshot_inner(
-        &mut self,
-        req: Request,
-    ) -> Oneshot<BoxCloneService<Request, Response, Infallible>, Request> {
-        self.0.get_mut().unwrap().clone().oneshot(req)
+    fn describe(&self) -> RouteKind {
+        self.inner.describe()
     }
 
-    //pub(crate) fn layer<L>(self, layer: L) -> Route
-    //where
-    //    L: Layer<Route> + Clone + Send + 'static,
-    //    L::Service: Service<Request, Error = Infallible> + Clone + Send + 'static,
-    //    <L::Service as Service<Request>>::Response: IntoResponse + 'static,
-    //    <L::Service as Service<Request>>::Future: Send + 'static,
-    //{
-    //    Route::new(layer.layer(self))
-    //}
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        self.inner.document(path, method, doc)
+    }
 }
 
-impl<E> Clone for Route<E> {
-    #[track_caller]
-    fn clone(&self) -> Self {
-        Self(Mutex::new(self.0.lock().unwrap().clone()))
+/// A route as seen by a layer added with [`PathRouter::route_layer`].
+///
+/// Cloning a route is cheap, every clone shares the same handler.
+#[derive(Clone)]
+pub struct Route(Arc<dyn ErasedHandler>);
+
+impl Route {
+    pub(crate) fn call_route(&self, req: Request) -> RouteFuture {
+        self.0.call(req)
     }
 }
 
-impl<E> std::fmt::Debug for Route<E> {
+impl std::fmt::Debug for Route {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Route").finish()
     }
@@ -104,7 +143,7 @@ impl Service<Request> for Route {
 
     #[inline]
     fn call(&mut self, req: Request) -> Self::Future {
-        RouteFuture::from_future(self.oneshot_inner(req))
+        self.call_route(req)
     }
 }
 
@@ -123,10 +162,7 @@ pin_project! {
     enum RouteFutureKind {
         Future {
             #[pin]
-            future: Oneshot<
-                BoxCloneService<Request, Response, Infallible>,
-                Request,
-            >,
+            future: Pin<Box<dyn Future<Output = Response> + Send>>,
         },
         Response {
             response: Option<Response>,
@@ -135,11 +171,22 @@ pin_project! {
 }
 
 impl RouteFuture {
-    pub(crate) fn from_future(
-        future: Oneshot<BoxCloneService<Request, Response, Infallible>, Request>,
-    ) -> Self {
+    pub(crate) fn from_future<F>(future: F) -> Self
+    where
+        F: Future<Output = Response> + Send + 'static,
+    {
         Self {
-            kind: RouteFutureKind::Future { future },
+            kind: RouteFutureKind::Future { future: Box::pin(future) },
+            strip_body: false,
+            allow_header: None,
+        }
+    }
+}
+
+impl RouteFuture {
+    pub(crate) fn from_response(response: Response) -> Self {
+        Self {
+            kind: RouteFutureKind::Response { response: Some(response) },
             strip_body: false,
             allow_header: None,
         }
@@ -155,8 +202,7 @@ impl Future for RouteFuture {
 
         let mut res = match this.kind.project() {
             RouteFutureKindProj::Future { future } => match future.poll(cx) {
-                Poll::Ready(Ok(res)) => res,
-                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Ready(res) => res,
                 Poll::Pending => return Poll::Pending,
             },
             RouteFutureKindProj::Response { response } => {
@@ -205,50 +251,72 @@ fn set_content_length(size_hint: SizeHint, headers: &mut HeaderMap) {
     }
 }
 
-pub trait ErasedHandler {
-    fn clone_box(&self) -> Box<dyn ErasedHandler + Send>;
-    fn into_route(self: Box<Self>) -> Route;
-    #[allow(dead_code)]
-    fn call(self: Box<Self>, request: Request) -> RouteFuture;
-}
-
-impl<H> ErasedHandler for MakeErasedHandler<H>
-where
-    H: Clone + Send + 'static,
-{
-    fn clone_box(&self) -> Box<dyn ErasedHandler + Send> {
-        Box::new(self.clone())
-    }
-
-    fn into_route(self: Box<Self>) -> Route {
-        (self.into_route)(self.handler)
-    }
-
-    fn call(self: Box<Self>, req: Request) -> RouteFuture {
-        self.into_route().call(req)
-    }
-}
-
-pub struct BoxedRoute(Mutex<Box<dyn ErasedHandler + Send>>);
+/// A handler compiled into a route when it is registered. Clones share the handler, so calling a
+/// route doesn't clone or lock anything besides what the handler itself needs.
+#[derive(Clone)]
+pub struct BoxedRoute(Arc<dyn ErasedHandler>);
 impl BoxedRoute {
     pub fn new<H, T>(handler: H) -> Self
     where
         H: Handler<T>,
         T: 'static,
     {
-        Self(Mutex::new(Box::new(MakeErasedHandler {
+        // Endpoints and routers are called by reference, other handlers are cloned for every
+        // request since calling a handler consumes it
+        let handler = match try_downcast::<Endpoint, _>(handler) {
+            Ok(endpoint) => return Self(Arc::new(endpoint)),
+            Err(handler) => handler,
+        };
+        let handler = match try_downcast::<PathRouter, _>(handler) {
+            Ok(router) => return Self(Arc::new(router)),
+            Err(handler) => handler,
+        };
+        Self(Arc::new(MakeErasedHandler {
             handler,
-            into_route: |handler| Route::new(handler.into_service()),
-        })))
+            call: |handler, req| RouteFuture::from_future(handler.clone().call(req)),
+            accepts: |handler, req| handler.accepts(req),
+            describe: |handler| handler.describe(),
+            document: |handler, path, method, doc| handler.document(path, method, doc),
+        }))
     }
 
-    pub(crate) fn into_route(self) -> Route {
-        self.0.into_inner().unwrap().into_route()
+    pub(crate) fn call(&self, req: Request) -> RouteFuture {
+        self.0.call(req)
+    }
+
+    pub(crate) fn accepts(&self, req: &Request) -> bool {
+        self.0.accepts(req)
+    }
+
+    pub(crate) fn describe(&self) -> RouteKind {
+        self.0.describe()
+    }
+
+    pub(crate) fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        self.0.document(path, method, doc)
+    }
+
+    pub(crate) fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        let service = layer.layer(Route(self.0.clone()));
+        Self(Arc::new(MakeErasedLayered {
+            inner: self,
+            service,
+        }))
     }
 }
-impl Clone for BoxedRoute {
-    fn clone(&self) -> Self {
-        Self(Mutex::new(self.0.lock().unwrap().clone_box()))
+
+/// Take the value as a `T` if that is it's type
+fn try_downcast<T: 'static, K: 'static>(value: K) -> Result<T, K> {
+    let mut value = Some(value);
+    match <dyn Any>::downcast_mut::<Option<T>>(&mut value) {
+        Some(downcast) => Ok(downcast.take().unwrap()),
+        None => Err(value.unwrap()),
     }
 }
 
@@ -269,16 +337,39 @@ pub struct Endpoint {
     options: Option<BoxedRoute>,
     trace: Option<BoxedRoute>,
     patch: Option<BoxedRoute>,
+    /// Handlers for extension methods like `PROPFIND` or `MKCOL`
+    extensions: Vec<(Method, BoxedRoute)>,
     fallback: Option<BoxedRoute>,
 }
 
+/// A set of standard methods used to register a handler for multiple methods at once.
+///
+/// ```ignore
+/// methods::on(MethodFilter::GET | MethodFilter::HEAD, handler)
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MethodFilter(u16);
+bitflags::bitflags! {
+    impl MethodFilter: u16 {
+        const GET = 1;
+        const HEAD = 1 << 1;
+        const POST = 1 << 2;
+        const PUT = 1 << 3;
+        const DELETE = 1 << 4;
+        const CONNECT = 1 << 5;
+        const OPTIONS = 1 << 6;
+        const TRACE = 1 << 7;
+        const PATCH = 1 << 8;
+    }
+}
+
 macro_rules! impl_endpoint_methods {
     ($($method: ident),* $(,)?) => {
         impl Endpoint {
             $(
                 pub fn $method<H, D>(mut self, handler: H) -> Self
                 where
-                    H: Handler<D> + Send + 'static,
+                    H: Handler<D> + Clone + Send + Sync + 'static,
                     D: 'static
                 {
                     self.$method = Some(BoxedRoute::new(handler));
@@ -288,20 +379,140 @@ macro_rules! impl_endpoint_methods {
 
             pub fn fallback<H, D>(mut self, handler: H) -> Self
             where
-                H: Handler<D> + Send + 'static,
+                H: Handler<D> + Clone + Send + Sync + 'static,
                 D: 'static
             {
                 self.fallback = Some(BoxedRoute::new(handler));
                 self
             }
+
+            paste::paste! {
+                $(
+                    /// Apply a layer to the handler currently registered for this method.
+                    ///
+                    /// The layer only runs for requests with this method and is a no-op if
+                    /// no handler has been registered for it yet.
+                    pub fn [<$method _layer>]<L>(mut self, layer: L) -> Self
+                    where
+                        L: Layer<Route> + Clone + Send + 'static,
+                        L::Service: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+                        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+                        <L::Service as Service<Request>>::Future: Send + 'static,
+                    {
+                        self.$method = self.$method.map(|route| route.layer(layer));
+                        self
+                    }
+                )*
+            }
+
+            /// Apply a layer to every method handler that is currently registered.
+            ///
+            /// The fallback is left untouched so requests with unhandled methods are not
+            /// affected by the layer.
+            pub fn route_layer<L>(mut self, layer: L) -> Self
+            where
+                L: Layer<Route> + Clone + Send + 'static,
+                L::Service: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+                <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+                <L::Service as Service<Request>>::Future: Send + 'static,
+            {
+                $(self.$method = self.$method.map(|route| route.layer(layer.clone()));)*
+                self.extensions = self.extensions
+                    .into_iter()
+                    .map(|(method, route)| (method, route.layer(layer.clone())))
+                    .collect();
+                self
+            }
+
+            /// Register a handler for every method in the filter.
+            pub fn on<H, D>(mut self, filter: MethodFilter, handler: H) -> Self
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                let route = BoxedRoute::new(handler);
+                paste::paste! {
+                    $(
+                        if filter.contains(MethodFilter::[<$method:upper>]) {
+                            self.$method = Some(route.clone());
+                        }
+                    )*
+                }
+                self
+            }
+
+            /// Register a handler for a single method. This can be any method, including
+            /// extension methods like `PROPFIND`.
+            pub fn on_method<H, D>(mut self, method: Method, handler: H) -> Self
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                let route = BoxedRoute::new(handler);
+                paste::paste! {
+                    $(
+                        if method == Method::[<$method:upper>] {
+                            self.$method = Some(route);
+                            return self;
+                        }
+                    )*
+                }
+                self.extensions.retain(|(m, _)| *m != method);
+                self.extensions.push((method, route));
+                self
+            }
+
+            /// Register a handler for every method, including extension methods.
+            ///
+            /// This replaces any handlers that were already registered.
+            pub fn any<H, D>(mut self, handler: H) -> Self
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                let route = BoxedRoute::new(handler);
+                $(self.$method = Some(route.clone());)*
+                self.extensions.clear();
+                self.fallback = Some(route);
+                self
+            }
         }
 
         pub mod methods {
-            use super::{Endpoint, Handler, BoxedRoute};
+            use hyper::Method;
+            use super::{Endpoint, Handler, BoxedRoute, MethodFilter};
+
+            /// Serve every method, including extension methods, with the handler.
+            pub fn any<H, D>(handler: H) -> Endpoint
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                Endpoint::default().any(handler)
+            }
+
+            /// Serve every method in the filter with the handler.
+            pub fn on<H, D>(filter: MethodFilter, handler: H) -> Endpoint
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                Endpoint::default().on(filter, handler)
+            }
+
+            /// Serve a single method, which can be an extension method like `PROPFIND`.
+            pub fn on_method<H, D>(method: Method, handler: H) -> Endpoint
+            where
+                H: Handler<D> + Clone + Send + Sync + 'static,
+                D: 'static
+            {
+                Endpoint::default().on_method(method, handler)
+            }
+
             $(
                 pub fn $method<H, D>(handler: H) -> Endpoint
                 where
-                    H: Handler<D> + Send + Sync + 'static,
+                    H: Handler<D> + Clone + Send + Sync + 'static,
                     D: 'static
                 {
                     Endpoint {
@@ -316,56 +527,132 @@ macro_rules! impl_endpoint_methods {
 
 impl_endpoint_methods!(get, post, put, delete, options, head, patch, trace, connect);
 
+impl Endpoint {
+    fn slot(&self, method: &Method) -> Option<&BoxedRoute> {
+        match *method {
+            Method::GET => self.get.as_ref(),
+            Method::POST => self.post.as_ref(),
+            Method::PUT => self.put.as_ref(),
+            Method::DELETE => self.delete.as_ref(),
+            Method::OPTIONS => self.options.as_ref(),
+            Method::HEAD => self.head.as_ref(),
+            Method::PATCH => self.patch.as_ref(),
+            Method::TRACE => self.trace.as_ref(),
+            Method::CONNECT => self.connect.as_ref(),
+            _ => self.extensions
+                .iter()
+                .find(|(m, _)| m == method)
+                .map(|(_, route)| route),
+        }
+    }
+}
+
 impl Handler<Endpoint> for Endpoint {
     type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
 
     fn call(self, req: Request) -> Self::Future {
-        let handler = match *req.method() {
-            Method::GET => self.get.clone(),
-            Method::POST => self.post.clone(),
-            Method::PUT => self.put.clone(),
-            Method::DELETE => self.delete.clone(),
-            Method::OPTIONS => self.options.clone(),
-            Method::HEAD => self.head.clone(),
-            Method::PATCH => self.patch.clone(),
-            Method::TRACE => self.trace.clone(),
-            Method::CONNECT => self.connect.clone(),
-            _ => None,
-        };
+        let future = ErasedHandler::call(&self, req);
+        Box::pin(async move { future.await.unwrap() })
+    }
 
-        match handler {
-            Some(handler) => Box::pin(async move { handler.into_route().call(req).await.unwrap() }),
-            None => {
-                if let Some(fallback) = self.fallback.clone() {
-                    Box::pin(async move { fallback.into_route().call(req).await.unwrap() })
-                } else {
-                    Box::pin(async move {
-                        hyper::Response::builder()
-                            .status(404)
-                            .body(Body::empty())
-                            .unwrap()
-                    })
-                }
+    // Endpoints for the same path with different methods can be registered separately, so an
+    // endpoint without a handler for the method lets the router try the next match. The router
+    // still calls it, for a `405`, when nothing else matched.
+    fn accepts(&self, req: &Request) -> bool {
+        match (self.slot(req.method()), self.fallback.as_ref()) {
+            (Some(handler), _) => handler.accepts(req),
+            (None, Some(fallback)) => fallback.accepts(req),
+            (None, None) => false,
+        }
+    }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+
+    fn document(&self, path: &str, _method: Option<&Method>, doc: &mut OpenApi) {
+        for method in STANDARD_METHODS.iter() {
+            if let Some(route) = self.slot(method) {
+                route.document(path, Some(method), doc);
             }
         }
+        for (method, route) in self.extensions.iter() {
+            route.document(path, Some(method), doc);
+        }
+    }
+}
+
+const STANDARD_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::CONNECT,
+    Method::OPTIONS,
+    Method::TRACE,
+    Method::PATCH,
+];
+
+impl Describe for Endpoint {
+    fn describe(&self) -> RouteKind {
+        RouteKind::Endpoint {
+            methods: STANDARD_METHODS
+                .into_iter()
+                .filter(|method| self.slot(method).is_some())
+                .chain(self.extensions.iter().map(|(method, _)| method.clone()))
+                .collect(),
+            fallback: self.fallback.is_some(),
+        }
     }
 }
 
 impl Service<Request> for Endpoint {
     type Error = Infallible;
     type Response = Response;
-    type Future =
-        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+    type Future = RouteFuture;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
-        let handler = self.clone();
-        Box::pin(async move {
-            Ok(Handler::call(handler, req).await)
-        })
+        ErasedHandler::call(self, req)
+    }
+}
+
+/// Endpoints are called by reference when they are routed to, so they aren't cloned for every
+/// request
+impl ErasedHandler for Endpoint {
+    fn call(&self, req: Request) -> RouteFuture {
+        match self.slot(req.method()).or(self.fallback.as_ref()) {
+            Some(route) => route.call(req),
+            None => {
+                let RouteKind::Endpoint { methods, .. } = Describe::describe(self) else {
+                    unreachable!()
+                };
+                let allow = methods.iter().map(Method::as_str).collect::<Vec<_>>().join(", ");
+                RouteFuture::from_response(
+                    hyper::Response::builder()
+                        .status(405)
+                        .header(header::ALLOW, allow)
+                        .body(Body::empty())
+                        .unwrap()
+                )
+            },
+        }
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        Handler::accepts(self, req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+
+    fn document(&self, path: &str, method: Option<&Method>, doc: &mut OpenApi) {
+        Handler::document(self, path, method, doc)
     }
 }
 
@@ -374,8 +661,13 @@ impl Service<Request> for Endpoint {
 // Mainly used to match agains actual routes served from a request.
 #[derive(Debug, Clone)]
 pub struct RoutePath {
-    path: String,
+    path: Arc<str>,
     pattern: Regex,
+    // Names of the captures, shared with every request instead of allocated for each
+    names: Arc<[Option<Arc<str>>]>,
+    // The literal part of the pattern before the first capture, paths that don't start with it
+    // can be skipped without running the regex
+    prefix: usize,
 }
 
 impl RoutePath {
@@ -400,14 +692,23 @@ impl RoutePath {
             }
         }).collect::<Vec<String>>().join("/");
 
+        // The slash before a catch all is optional so it isn't part of the prefix
+        let prefix = match pattern.find(':') {
+            Some(i) => pattern[..i].trim_end_matches('/').len(),
+            None => pattern.len(),
+        };
+
+        let regex = Regex::new(format!("^{reg}$").as_str()).expect("Invalid uri path regex");
         Self {
-            path: pattern.to_string(),
-            pattern: Regex::new(format!("^{reg}$").as_str()).expect("Invalid uri path regex"),
+            path: Arc::from(pattern),
+            names: regex.capture_names().skip(1).map(|name| name.map(Arc::from)).collect(),
+            pattern: regex,
+            prefix,
         }
     }
 
     pub fn path(&self) -> &str {
-        self.path.as_str()
+        &self.path
     }
     
     /// Try to match the dynamic route path to the served uri
@@ -416,34 +717,223 @@ impl RoutePath {
     ///
     /// Some, if it matches with a list of captures from the url and a ranking based on how many characters where
     /// captured. None if it does not match. 
-    pub fn match_path<'a>(&'a self, path: &'a str) -> Option<(Vec<(&'a str, &'a str)>, usize)> {
-        self.pattern.captures(path).map(|captures| {
-            let captures = self.pattern.capture_names().skip(1).zip(captures.iter().skip(1)).map(|(name, capture)| {
-                (name.unwrap(), capture.unwrap().as_str())
-            });
-            let captures: Vec<(&'a str, &'a str)> = captures.collect();
-            let total = captures.iter().map(|v| v.1.len()).sum();
-            (captures, total)
-        })
+    pub(crate) fn match_captures<'a>(&self, path: &'a str) -> Option<(usize, Option<Captures<'a>>)> {
+        if !path.starts_with(&self.path[..self.prefix]) {
+            return None;
+        }
+        // Static paths are matched literally
+        if self.prefix == self.path.len() {
+            return (path == &*self.path).then_some((0, None));
+        }
+        if self.pattern.captures_len() == 1 {
+            return self.pattern.is_match(path).then_some((0, None));
+        }
+
+        let captures = self.pattern.captures(path)?;
+        let rank = captures.iter().skip(1).flatten().map(|capture| capture.len()).sum();
+        Some((rank, Some(captures)))
     }
 }
 
+/// A route generated by an attribute like `#[get("/users/:id")]`, mounted with
+/// [`PathRouter::mount`] or the [`routes!`](crate::routes) macro.
+pub trait Mount {
+    /// The route pattern
+    const PATH: &'static str;
+
+    fn mount(router: PathRouter) -> PathRouter;
+}
+
+/// Routes a request to a handler based on it's path.
+///
+/// The routes are shared between clones of the router, so serving a request doesn't copy the
+/// route table. Changing a router that has been cloned copies the routes first.
 #[derive(Default, Clone)]
 pub struct PathRouter {
+    inner: Arc<RouterInner>,
+}
+
+#[derive(Default, Clone)]
+struct RouterInner {
     paths: Vec<RoutePath>,
     routes: Vec<BoxedRoute>,
     fallback: Option<BoxedRoute>,
+    state: Arc<Extensions>,
+    substates: Vec<Substate>,
+    flags: RouterFlags,
+    trailing_slash: TrailingSlash,
+    catchers: Vec<Catcher>,
+    rejection_body: RejectionBody,
+    body_limit: Option<BodyLimit>,
+}
+
+/// Whether the route is an endpoint without a handler, or fallback, for the method
+fn lacks_method(route: &BoxedRoute, method: &Method) -> bool {
+    matches!(route.describe(), RouteKind::Endpoint { methods, fallback: false } if !methods.contains(method))
+}
+
+/// Derives a sub state from a state of the router, if it is there
+type Substate = Arc<dyn Fn(&mut Extensions) + Send + Sync>;
+
+impl RouterInner {
+    /// Sub states are derived again whenever a state changes, so they are never stale
+    fn derive_substates(&mut self) {
+        let state = Arc::make_mut(&mut self.state);
+        for derive in &self.substates {
+            derive(state);
+        }
+    }
+
+    /// The route with the lowest rank that matches the path and accepts the request. Routes that
+    /// were registered first win when the rank is the same.
+    ///
+    /// When no route accepts the request, an endpoint that matched the path but has no handler
+    /// for the method is used instead, so it can respond with `405 Method Not Allowed`.
+    fn find<'a>(&self, path: &'a str, req: &Request) -> Option<(usize, Option<Captures<'a>>)> {
+        let mut best: Option<(usize, usize, Option<Captures<'a>>)> = None;
+        let mut wrong_method: Option<(usize, usize, Option<Captures<'a>>)> = None;
+        for (i, route) in self.paths.iter().enumerate() {
+            if let Some((rank, captures)) = route.match_captures(path) {
+                let better = best.as_ref().is_none_or(|(_, best_rank, _)| rank < *best_rank);
+                // Guards are only checked when the route would be a better match, so a rejected
+                // route falls through to the next best match
+                if better && self.routes[i].accepts(req) {
+                    best = Some((i, rank, captures));
+                } else if better
+                    && wrong_method.as_ref().is_none_or(|(_, wrong_rank, _)| rank < *wrong_rank)
+                    && lacks_method(&self.routes[i], req.method())
+                {
+                    wrong_method = Some((i, rank, captures));
+                }
+            }
+        }
+        best.or(wrong_method).map(|(i, _, captures)| (i, captures))
+    }
 }
 
 impl PathRouter {
+    fn inner_mut(&mut self) -> &mut RouterInner {
+        Arc::make_mut(&mut self.inner)
+    }
+}
+
+impl PathRouter {
+    /// Handle error responses with a specific status, or range of statuses, that have an empty
+    /// body.
+    ///
+    /// This covers responses from handlers, extractor rejections, route layers and the builtin
+    /// `404`. The catcher is called with the original request parts, without the body, and can
+    /// use the [`Caught`](crate::extract::Caught) extractor to inspect the original response and
+    /// error. The original status is kept unless the catcher responds with an error status of
+    /// it's own.
+    ///
+    /// When multiple catchers match, the one with the narrowest range is used.
+    ///
+    /// ```ignore
+    /// PathRouter::default()
+    ///     .catch(404, not_found)
+    ///     .catch(StatusClass::ServerError, server_error)
+    /// ```
+    pub fn catch<R, H, D>(mut self, range: R, handler: H) -> Self
+    where
+        R: IntoStatusRange,
+        H: Handler<D> + Send + 'static,
+        D: 'static,
+    {
+        self.inner_mut().catchers.push(Catcher::new(range.into_status_range(), BoxedRoute::new(handler)));
+        self
+    }
+
+    /// Set how the body of extractor rejections and [`HttpError`](crate::HttpError)s is rendered,
+    /// they have an empty body by default.
+    ///
+    /// Catchers are applied first, so only rejections that weren't caught are rendered.
+    pub fn rejection_body(mut self, format: RejectionBody) -> Self {
+        self.inner_mut().rejection_body = format;
+        self
+    }
+
+    /// Set the maximum body size, in bytes, for the `Json`, `UrlEncoded`, `String` and `Bytes`
+    /// extractors of every route in this router. Larger bodies are rejected with
+    /// `413 Payload Too Large`.
+    ///
+    /// The default is [`DEFAULT_BODY_LIMIT`](crate::extract::DEFAULT_BODY_LIMIT), single routes
+    /// can change it with [`BodyLimitLayer`](crate::layer::BodyLimitLayer).
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.inner_mut().body_limit = Some(BodyLimit(Some(limit)));
+        self
+    }
+
+    /// Set how trailing slashes in request paths are handled.
+    ///
+    /// Redirects use `308 Permanent Redirect` so the request method and body are kept.
+    pub fn trailing_slash(mut self, policy: TrailingSlash) -> Self {
+        self.inner_mut().trailing_slash = policy;
+        self
+    }
+
+    /// Set which path normalizations are applied before routes are matched.
+    ///
+    /// Requests with a path that isn't normalized are redirected with `308 Permanent Redirect`
+    /// to the normalized path.
+    pub fn flags(mut self, flags: RouterFlags) -> Self {
+        self.inner_mut().flags = flags;
+        self
+    }
+
+    /// Share a value with every handler in this router, and any router nested in it.
+    ///
+    /// The value can be extracted with [`State`](crate::extract::State). Multiple states can be
+    /// added as long as they are different types.
+    pub fn with_state<S>(mut self, state: S) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let inner = self.inner_mut();
+        Arc::make_mut(&mut inner.state).insert(Shared(state));
+        inner.derive_substates();
+        self
+    }
+
+    /// Derive a sub state from a state previously added with [`PathRouter::with_state`].
+    ///
+    /// This allows handlers to extract `State<T>` instead of the entire application state. The
+    /// sub state follows the state, replacing the state with `with_state` derives it again.
+    ///
+    /// # Panics
+    ///
+    /// If the state `S` hasn't been added to the router yet.
+    pub fn with_substate<S, T>(mut self) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+        T: FromRef<S> + Clone + Send + Sync + 'static,
+    {
+        if self.inner.state.get::<Shared<S>>().is_none() {
+            panic!(
+                "state `{}` must be added with `with_state` before deriving a sub state from it",
+                std::any::type_name::<S>()
+            );
+        }
+        let inner = self.inner_mut();
+        inner.substates.push(Arc::new(|state: &mut Extensions| {
+            if let Some(Shared(parent)) = state.get::<Shared<S>>() {
+                let substate = T::from_ref(parent);
+                state.insert(Shared(substate));
+            }
+        }));
+        inner.derive_substates();
+        self
+    }
+
     pub fn route<S, H, D>(mut self, path: S, route: H) -> Self
     where
         S: AsRef<str>,
         H: Handler<D> + Send + 'static,
         D: 'static,
     {
-        self.paths.push(RoutePath::new(path.as_ref()));
-        self.routes.push(BoxedRoute::new(route));
+        let inner = self.inner_mut();
+        inner.paths.push(RoutePath::new(path.as_ref()));
+        inner.routes.push(BoxedRoute::new(route));
         self
     }
 
@@ -452,44 +942,241 @@ impl PathRouter {
         H: Handler<D> + Clone + Send + 'static,
         D: 'static,
     {
-        self.fallback = Some(BoxedRoute::new(handler));
+        self.inner_mut().fallback = Some(BoxedRoute::new(handler));
         self
     }
+
+    /// Serve a path with a [`Service`], for example a hyper service, a proxy or the router of
+    /// another framework.
+    ///
+    /// The service can't fail, use [`handle_error`] to turn the errors of a fallible service into
+    /// responses. It doesn't have to be `Sync`, the service is cloned for every request.
+    pub fn route_service<P, S>(self, path: P, service: S) -> Self
+    where
+        P: AsRef<str>,
+        S: Service<Request, Error = Infallible> + Clone + Send + 'static,
+        S::Response: IntoResponse,
+        S::Future: Send + 'static,
+    {
+        self.route(path, ServiceRoute::new(service))
+    }
+
+    /// Serve every path under a prefix with a [`Service`].
+    ///
+    /// The prefix is removed from the path before the request is forwarded, so a service nested
+    /// at `/legacy` sees a request to `/legacy/users` as `/users`.
+    pub fn nest_service<P, S>(self, prefix: P, service: S) -> Self
+    where
+        P: AsRef<str>,
+        S: Service<Request, Error = Infallible> + Clone + Send + 'static,
+        S::Response: IntoResponse,
+        S::Future: Send + 'static,
+    {
+        let prefix = prefix.as_ref().trim_end_matches('/');
+        self.route(format!("{prefix}/:*_"), ServiceRoute::nested(prefix, service))
+    }
+
+    /// Remove every route registered with the pattern, returns whether any route was removed.
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        if !self.inner.paths.iter().any(|path| path.path() == pattern) {
+            return false;
+        }
+
+        let inner = self.inner_mut();
+        let mut keep = inner.paths.iter().map(|path| path.path() != pattern);
+        inner.routes.retain(|_| keep.next().unwrap());
+        inner.paths.retain(|path| path.path() != pattern);
+        true
+    }
+
+    /// Add a route generated by a route attribute
+    ///
+    /// ```ignore
+    /// #[get("/users/:id")]
+    /// async fn user(Capture(id): Capture<u32>) -> String { ... }
+    ///
+    /// PathRouter::default().mount::<user>()
+    /// ```
+    pub fn mount<M: Mount>(self) -> Self {
+        M::mount(self)
+    }
+
+    /// Apply a layer to every route that is currently registered.
+    ///
+    /// Unlike [`Handler::layer`] the layer only runs when a route matched the request, so
+    /// something like authentication won't turn an unknown path into a `401`. Routes added after
+    /// this call are not layered.
+    pub fn route_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        let inner = self.inner_mut();
+        inner.routes = inner.routes
+            .drain(..)
+            .map(|route| route.layer(layer.clone()))
+            .collect();
+        self
+    }
+}
+
+impl PathRouter {
+    /// List every route registered on the router, including nested routers and the fallback.
+    pub fn routes(&self) -> RouteTable {
+        RouteTable {
+            routes: self.inner.paths.iter().zip(self.inner.routes.iter()).map(|(path, route)| RouteInfo {
+                pattern: path.path().to_string(),
+                kind: route.describe(),
+            }).collect(),
+            fallback: self.inner.fallback.as_ref().map(|fallback| Box::new(fallback.describe())),
+        }
+    }
+}
+
+impl PathRouter {
+    /// Generate an OpenAPI document from the routes that are documented with
+    /// [`openapi::doc`](crate::openapi::doc).
+    pub fn openapi(&self, info: Info) -> OpenApi {
+        let mut doc = OpenApi::new(info);
+        self.document_routes(&mut doc);
+        doc
+    }
+
+    fn document_routes(&self, doc: &mut OpenApi) {
+        for (path, route) in self.inner.paths.iter().zip(self.inner.routes.iter()) {
+            route.document(path.path(), None, doc);
+        }
+    }
+
+    /// Serve the OpenAPI document of the currently registered routes as JSON, and optionally a
+    /// Swagger UI page to browse it.
+    ///
+    /// The document is generated when this is called, so it should be called after all documented
+    /// routes have been added.
+    ///
+    /// The Swagger UI page loads it's scripts and styles from
+    /// [`SWAGGER_UI_ASSETS`](crate::openapi::SWAGGER_UI_ASSETS), to serve them yourself use
+    /// [`openapi::swagger_ui`](crate::openapi::swagger_ui) for the page instead.
+    pub fn with_openapi<S: AsRef<str>>(self, spec: S, ui: Option<S>, info: Info) -> Self {
+        let document = Arc::new(self.openapi(info).to_json());
+        let spec = spec.as_ref().to_string();
+        let mut router = self.route(spec.as_str(), methods::get(move || {
+            let document = document.clone();
+            async move { Json(document.as_ref().clone()) }
+        }));
+
+        if let Some(ui) = ui {
+            let page = openapi::swagger_ui(spec.as_str(), openapi::SWAGGER_UI_ASSETS);
+            router = router.route(ui, methods::get(move || {
+                let page = page.clone();
+                async move { Html(page) }
+            }));
+        }
+        router
+    }
+}
+
+impl Describe for PathRouter {
+    fn describe(&self) -> RouteKind {
+        RouteKind::Router(self.routes())
+    }
 }
 
 impl Handler<PathRouter> for PathRouter {
     type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
 
-    fn call(self, mut req: Request) -> Self::Future {
-        let path = req.uri().path().to_string();
-        let mut matches = Vec::new();
-        for (i, route) in self.paths.iter().enumerate() {
-            if let Some((captures, rank)) = route.match_path(path.as_str()) {
-                matches.push((i, captures, rank));
+    fn call(self, req: Request) -> Self::Future {
+        let future = ErasedHandler::call(&self, req);
+        Box::pin(async move { future.await.unwrap() })
+    }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+
+    fn document(&self, _path: &str, _method: Option<&Method>, doc: &mut OpenApi) {
+        self.document_routes(doc)
+    }
+}
+
+/// Routers are called by reference, nested ones included, so a request doesn't clone the router or
+/// box the future of the route again
+impl ErasedHandler for PathRouter {
+    fn call(&self, mut req: Request) -> RouteFuture {
+        let inner = &self.inner;
+        if !inner.state.is_empty() {
+            let outer = req.extensions_mut().remove::<RouterState>().map(Arc::new);
+            req.extensions_mut().insert(RouterState { state: inner.state.clone(), outer });
+        }
+        if let Some(limit) = inner.body_limit {
+            req.extensions_mut().insert(limit);
+        }
+
+        let path = req.uri().path();
+        if let Cow::Owned(normalized) = normalize::normalize(path, inner.flags, inner.trailing_slash) {
+            let location = match req.uri().query() {
+                Some(query) => format!("{normalized}?{query}"),
+                None => normalized,
+            };
+            return RouteFuture::from_response(Redirect::permanent_redirect(location).into_response());
+        }
+
+        let mut found = inner.find(path, &req).map(|(i, captures)| (i, decode_params(&inner.paths[i], captures)));
+        // Both forms of the path are the same when trailing slashes are ignored
+        if found.is_none() && inner.trailing_slash == TrailingSlash::Ignore {
+            if let Some(toggled) = TrailingSlash::toggle(path) {
+                found = inner.find(toggled.as_str(), &req).map(|(i, captures)| (i, decode_params(&inner.paths[i], captures)));
             }
         }
-        matches.sort_by(|(_, _, a), (_, _, b)| a.cmp(b));
 
-        let best = matches.first();
-        match best {
-            Some((i, captures, _)) => {
-                let route = self.routes.get(*i).unwrap().clone();
+        let route = match found {
+            Some((i, params)) => {
                 // Add captures and original path to request extensions to be used in extractors
                 // later
-                insert_url_params(req.extensions_mut(), captures);
-                Box::pin(async move { route.into_route().call(req).await.unwrap() })
+                insert_url_params(req.extensions_mut(), params);
+                req.extensions_mut().insert(MatchedPath(inner.paths[i].path.clone()));
+                Some(&inner.routes[i])
             },
-            None => if let Some(fallback) = self.fallback.clone() {
-                Box::pin(async move { fallback.into_route().call(req).await.unwrap() })
-            } else {
-                Box::pin(async move {
-                    hyper::Response::builder()
-                        .status(404)
-                        .body(Body::empty())
-                        .unwrap()
-                })
-            }
+            None => inner.fallback.as_ref(),
+        };
+
+        if inner.catchers.is_empty() && inner.rejection_body == RejectionBody::Empty {
+            return match route {
+                Some(route) => route.call(req),
+                None => RouteFuture::from_response(not_found()),
+            };
         }
+
+        // The request head is only kept around when it could be needed by a catcher
+        let (parts, body) = req.into_parts();
+        let saved = (!inner.catchers.is_empty()).then(|| parts.clone());
+        let future = route.map(|route| route.call(Request::from_parts(parts, body)));
+        let inner = inner.clone();
+
+        RouteFuture::from_future(async move {
+            let mut response = match future {
+                Some(future) => future.await.unwrap(),
+                None => not_found(),
+            };
+            if let Some(saved) = saved {
+                response = catcher::catch(&inner.catchers, saved, response).await;
+            }
+            render_rejection(inner.rejection_body, response)
+        })
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        Handler::accepts(self, req)
+    }
+
+    fn describe(&self) -> RouteKind {
+        Describe::describe(self)
+    }
+
+    fn document(&self, _path: &str, _method: Option<&Method>, doc: &mut OpenApi) {
+        self.document_routes(doc)
     }
 }
 
@@ -500,23 +1187,46 @@ where
 {
     type Response = Response;
     type Error = std::convert::Infallible;
-    type Future =
-        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+    type Future = RouteFuture;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, req: Request<B>) -> Self::Future {
-        let handler = self.clone();
-        let req = req.map(Body::new);
-        Box::pin(async move {
-            Ok(Handler::call(handler, req).await)
-        })
+        ErasedHandler::call(self, req.map(Body::new))
     }
 }
 
-fn insert_url_params(extensions: &mut Extensions, params: &[(&str, &str)]) {
+fn not_found() -> Response {
+    hyper::Response::builder()
+        .status(404)
+        .body(Body::empty())
+        .unwrap()
+}
+
+type UrlParams = Result<Vec<(Arc<str>, PercentDecodedStr)>, Arc<str>>;
+
+/// Percent decode the named captures of a matched route, or the name of the first capture that
+/// isn't valid UTF-8 after decoding.
+fn decode_params(path: &RoutePath, captures: Option<Captures<'_>>) -> UrlParams {
+    let captures = match captures {
+        Some(captures) => captures,
+        None => return Ok(Vec::new()),
+    };
+
+    path.names
+        .iter()
+        .zip(captures.iter().skip(1))
+        .filter_map(|(name, capture)| Some((name.as_ref()?, capture?.as_str())))
+        .map(|(k, v)| match PercentDecodedStr::new(v) {
+            Some(decoded) => Ok((k.clone(), decoded)),
+            None => Err(k.clone()),
+        })
+        .collect()
+}
+
+fn insert_url_params(extensions: &mut Extensions, params: UrlParams) {
     let current = extensions.get_mut::<UriParams>();
 
     // If there was an error in a prefious extraction then do nothing
@@ -524,16 +1234,6 @@ fn insert_url_params(extensions: &mut Extensions, params: &[(&str, &str)]) {
         return;
     }
 
-    let params = params
-        .iter()
-        .map(|(k, v)| {
-            if let Some(decoded) = PercentDecodedStr::new(v) {
-                Ok((Arc::from(*k), decoded))
-            } else {
-                Err(Arc::from(*k))
-            }
-        })
-        .collect::<Result<Vec<_>, _>>();
     match (current, params) {
         (_, Err(key)) => {
             extensions.insert(UriParams::InvalidEncoding(key));
@@ -547,3 +1247,58 @@ fn insert_url_params(extensions: &mut Extensions, params: &[(&str, &str)]) {
         _ => unreachable!("Should never reach this point becuase of prevous check for invalid encoding")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{header, StatusCode};
+
+    use super::*;
+
+    async fn ok() -> &'static str {
+        "ok"
+    }
+
+    async fn send(router: &PathRouter, method: Method, path: &str) -> Response {
+        let request = Request::builder().method(method).uri(path).body(Body::empty()).unwrap();
+        Handler::call(router.clone(), request).await
+    }
+
+    #[tokio::test]
+    async fn wrong_method_is_not_allowed() {
+        let router = PathRouter::default().route("/users", methods::get(ok).post(ok));
+        let response = send(&router, Method::DELETE, "/users").await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[header::ALLOW], "GET, POST");
+        assert_eq!(send(&router, Method::DELETE, "/posts").await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn methods_can_be_registered_separately() {
+        let router = PathRouter::default()
+            .route("/users", methods::get(ok))
+            .route("/users", methods::post(ok));
+        assert_eq!(send(&router, Method::GET, "/users").await.status(), StatusCode::OK);
+        assert_eq!(send(&router, Method::POST, "/users").await.status(), StatusCode::OK);
+        assert_eq!(send(&router, Method::PUT, "/users").await.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn nested_routers_share_their_state() {
+        use http_body_util::BodyExt;
+
+        use crate::extract::State;
+
+        let api = PathRouter::default()
+            .route("/api/state", methods::get(|State(n): State<u32>, State(name): State<String>| async move {
+                format!("{n} {name}")
+            }))
+            .with_state("inner".to_string());
+        let router = PathRouter::default()
+            .route("/api/:*_", api)
+            .with_state(5u32)
+            .with_state("outer".to_string());
+
+        let body = send(&router, Method::GET, "/api/state").await.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "5 inner");
+    }
+}
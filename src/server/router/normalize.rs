@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+/// Path normalization applied by a [`PathRouter`](super::PathRouter) before matching routes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RouterFlags(u8);
+bitflags::bitflags! {
+    impl RouterFlags: u8 {
+        /// Collapse repeated slashes, `/a//b` becomes `/a/b`
+        const MergeSlashes = 1;
+        /// Resolve `.` and `..` segments, `/a/./b/../c` becomes `/a/c`
+        const ResolveDots = 1 << 1;
+    }
+}
+
+/// How a [`PathRouter`](super::PathRouter) treats a trailing slash at the end of a request path.
+///
+/// The root path `/` is never changed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// `/a` and `/a/` are different paths
+    #[default]
+    Strict,
+    /// Redirect `/a` to `/a/`
+    Append,
+    /// Redirect `/a/` to `/a`
+    Trim,
+    /// `/a` and `/a/` both match the same routes without a redirect
+    Ignore,
+}
+
+impl TrailingSlash {
+    /// The same path with the trailing slash toggled. Used to match both forms of a path.
+    pub(crate) fn toggle(path: &str) -> Option<String> {
+        if path == "/" || path.is_empty() {
+            None
+        } else if let Some(trimmed) = path.strip_suffix('/') {
+            Some(trimmed.to_string())
+        } else {
+            Some(format!("{path}/"))
+        }
+    }
+}
+
+/// Normalize a request path based on the routers policies.
+///
+/// Returns the path that the client should be redirected to, if it differs from the original.
+pub(crate) fn normalize<'a>(path: &'a str, flags: RouterFlags, slash: TrailingSlash) -> Cow<'a, str> {
+    let mut result = Cow::Borrowed(path);
+
+    if flags.contains(RouterFlags::MergeSlashes) && result.contains("//") {
+        let mut merged = String::with_capacity(result.len());
+        let mut last = None;
+        for c in result.chars() {
+            if !(c == '/' && last == Some('/')) {
+                merged.push(c);
+            }
+            last = Some(c);
+        }
+        result = Cow::Owned(merged);
+    }
+
+    if flags.contains(RouterFlags::ResolveDots)
+        && result.split('/').any(|segment| segment == "." || segment == "..")
+    {
+        let trailing = result.ends_with("/.") || result.ends_with("/..") || result.ends_with('/');
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in result.split('/').skip(1) {
+            match segment {
+                "." => {},
+                // Never resolve above the root
+                ".." => { segments.pop(); },
+                segment => segments.push(segment),
+            }
+        }
+        // Remove empty segment from a trailing slash, it is added back below
+        if segments.last() == Some(&"") {
+            segments.pop();
+        }
+
+        let mut resolved = format!("/{}", segments.join("/"));
+        if trailing && resolved != "/" {
+            resolved.push('/');
+        }
+        result = Cow::Owned(resolved);
+    }
+
+    if result != "/" {
+        match slash {
+            TrailingSlash::Append if !result.ends_with('/') => {
+                result = Cow::Owned(format!("{result}/"));
+            },
+            TrailingSlash::Trim if result.ends_with('/') => {
+                result = Cow::Owned(result.trim_end_matches('/').to_string());
+                if result.is_empty() {
+                    result = Cow::Borrowed("/");
+                }
+            },
+            _ => {}
+        }
+    }
+
+    result
+}
@@ -0,0 +1,192 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use hyper::Uri;
+use tower::{Service, ServiceExt};
+
+use crate::{extract::IntoResponse, Request, Response};
+
+use super::{Handler, RouteKind};
+
+/// A [`Service`] mounted as a route with [`PathRouter::route_service`](super::PathRouter::route_service).
+///
+/// Routes are shared between threads, so the service is kept behind a lock and cloned for every
+/// request. This way services that aren't `Sync`, like a `BoxCloneService`, can be mounted.
+#[derive(Clone)]
+pub struct ServiceRoute<S> {
+    service: Arc<Mutex<S>>,
+    // Stripped from the path before the request is forwarded, for nested services
+    prefix: Option<Arc<str>>,
+}
+
+impl<S> std::fmt::Debug for ServiceRoute<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceRoute")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> ServiceRoute<S> {
+    pub(crate) fn new(service: S) -> Self {
+        Self { service: Arc::new(Mutex::new(service)), prefix: None }
+    }
+
+    pub(crate) fn nested(prefix: &str, service: S) -> Self {
+        Self {
+            service: Arc::new(Mutex::new(service)),
+            prefix: Some(Arc::from(prefix)),
+        }
+    }
+}
+
+/// The rest of the path after the prefix, `None` if the prefix doesn't end at a segment boundary.
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    match path.strip_prefix(prefix)? {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+impl<S> Handler<ServiceRoute<S>> for ServiceRoute<S>
+where
+    S: Service<Request, Error = Infallible> + Clone + Send + 'static,
+    S::Response: IntoResponse,
+    S::Future: Send + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, mut req: Request) -> Self::Future {
+        if let Some(prefix) = self.prefix.as_deref() {
+            let path = strip_prefix(req.uri().path(), prefix).unwrap_or("/");
+            let path_and_query = match req.uri().query() {
+                Some(query) => format!("{path}?{query}"),
+                None => path.to_string(),
+            };
+
+            let mut parts = req.uri().clone().into_parts();
+            parts.path_and_query = path_and_query.parse().ok();
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *req.uri_mut() = uri;
+            }
+        }
+
+        let service = self.service.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        Box::pin(async move {
+            match service.oneshot(req).await {
+                Ok(response) => response.into_response(),
+                Err(never) => match never {},
+            }
+        })
+    }
+
+    fn accepts(&self, req: &Request) -> bool {
+        match self.prefix.as_deref() {
+            Some(prefix) => strip_prefix(req.uri().path(), prefix).is_some(),
+            None => true,
+        }
+    }
+
+    fn describe(&self) -> RouteKind {
+        RouteKind::Named("Service")
+    }
+}
+
+/// A service that turns the errors of a fallible service into responses.
+///
+/// Created with [`handle_error`].
+#[derive(Clone)]
+pub struct HandleError<S, F> {
+    service: S,
+    handler: F,
+}
+
+impl<S, F> std::fmt::Debug for HandleError<S, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandleError").finish_non_exhaustive()
+    }
+}
+
+/// Map the errors of a fallible service to responses so it can be mounted with
+/// [`PathRouter::route_service`](super::PathRouter::route_service).
+///
+/// ```ignore
+/// PathRouter::default()
+///     .route_service("/proxy", handle_error(proxy, |err: ProxyError| async move {
+///         (StatusCode::BAD_GATEWAY, err.to_string())
+///     }))
+/// ```
+pub fn handle_error<S, F, Fut, R>(service: S, handler: F) -> HandleError<S, F>
+where
+    S: Service<Request>,
+    F: Fn(S::Error) -> Fut,
+    Fut: Future<Output = R>,
+    R: IntoResponse,
+{
+    HandleError { service, handler }
+}
+
+impl<S, F, Fut, R> Service<Request> for HandleError<S, F>
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Response: IntoResponse + Send,
+    S::Future: Send + 'static,
+    S::Error: Send,
+    F: Fn(S::Error) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = R> + Send,
+    R: IntoResponse,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let service = self.service.clone();
+        let handler = self.handler.clone();
+        Box::pin(async move {
+            Ok(match service.oneshot(req).await {
+                Ok(response) => response.into_response(),
+                Err(err) => handler(err).await.into_response(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+    use tower::util::BoxCloneService;
+
+    use super::*;
+    use crate::{server::PathRouter, Body};
+
+    async fn body(router: &PathRouter, path: &str) -> String {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        let response = Handler::call(router.clone(), request).await;
+        String::from_utf8(response.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn services_do_not_have_to_be_sync() {
+        let service = BoxCloneService::new(tower::service_fn(|req: Request| async move {
+            Ok::<_, Infallible>(req.uri().path().to_string())
+        }));
+        let router = PathRouter::default()
+            .route_service("/service", service.clone())
+            .nest_service("/legacy", service);
+
+        assert_eq!(body(&router, "/service").await, "/service");
+        assert_eq!(body(&router, "/legacy/users").await, "/users");
+        assert_eq!(body(&router, "/legacy").await, "/");
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt::{Display, Write as _};
+
+use hyper::Method;
+
+/// What is served by a registered route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteKind {
+    /// A handler that serves every method
+    Handler,
+    /// A builtin handler, like a [`FileRouter`](super::FileRouter), that serves every method
+    Named(&'static str),
+    /// An [`Endpoint`](super::Endpoint) that serves specific methods
+    Endpoint {
+        methods: Vec<Method>,
+        fallback: bool,
+    },
+    /// A nested [`PathRouter`](super::PathRouter)
+    Router(RouteTable),
+}
+
+/// A single route registered on a [`PathRouter`](super::PathRouter).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    pub pattern: String,
+    pub kind: RouteKind,
+}
+
+impl RouteInfo {
+    /// The methods this route serves. An empty list means every method is served.
+    pub fn methods(&self) -> &[Method] {
+        match &self.kind {
+            RouteKind::Endpoint { methods, .. } => methods.as_slice(),
+            _ => &[],
+        }
+    }
+}
+
+/// A snapshot of every route registered on a [`PathRouter`](super::PathRouter).
+///
+/// The [`Display`] implementation pretty prints the table so it can be logged.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RouteTable {
+    pub routes: Vec<RouteInfo>,
+    pub fallback: Option<Box<RouteKind>>,
+}
+
+impl RouteTable {
+    /// Every pattern in the table including the patterns of nested routers.
+    pub fn patterns(&self) -> Vec<&str> {
+        self.routes.iter().flat_map(|route| {
+            let mut patterns = vec![route.pattern.as_str()];
+            if let RouteKind::Router(nested) = &route.kind {
+                patterns.extend(nested.patterns());
+            }
+            patterns
+        }).collect()
+    }
+
+    /// Prefixes of nested routers
+    pub fn nested(&self) -> Vec<&str> {
+        self.routes.iter()
+            .filter(|route| matches!(route.kind, RouteKind::Router(_)))
+            .map(|route| route.pattern.as_str())
+            .collect()
+    }
+
+    fn method_column(kind: &RouteKind) -> String {
+        match kind {
+            RouteKind::Handler | RouteKind::Named(_) | RouteKind::Router(_) => "*".to_string(),
+            RouteKind::Endpoint { methods, fallback } => {
+                let mut methods = methods.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(",");
+                if *fallback {
+                    if !methods.is_empty() {
+                        methods.push(',');
+                    }
+                    methods.push('*');
+                }
+                methods
+            }
+        }
+    }
+
+    fn write_table(&self, f: &mut String, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        let width = self.routes.iter()
+            .map(|route| Self::method_column(&route.kind).len())
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        for route in self.routes.iter() {
+            let methods = Self::method_column(&route.kind);
+            write!(f, "{indent}{methods:<width$}  {}", route.pattern)?;
+            match &route.kind {
+                RouteKind::Named(name) => writeln!(f, " ({name})")?,
+                RouteKind::Router(nested) => {
+                    writeln!(f, " (router)")?;
+                    nested.write_table(f, depth + 1)?;
+                },
+                _ => writeln!(f)?,
+            }
+        }
+
+        if let Some(fallback) = &self.fallback {
+            let methods = Self::method_column(fallback);
+            writeln!(f, "{indent}{methods:<width$}  (fallback)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RouteTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = String::new();
+        self.write_table(&mut table, 0)?;
+        write!(f, "{}", table.trim_end())
+    }
+}
+
+/// Describe what a router or service serves so it can be listed in a [`RouteTable`].
+pub trait Describe {
+    fn describe(&self) -> RouteKind;
+}
@@ -1,5 +1,5 @@
 use std::{
-    future::Future, path::PathBuf, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}, fmt::{Debug, Display}, collections::HashMap,
+    future::Future, path::PathBuf, pin::Pin, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, task::{Context, Poll}, fmt::{Debug, Display}, collections::HashMap,
     any::type_name, ops::Deref,
 };
 
@@ -8,13 +8,17 @@ use http_body_util::BodyExt;
 use serde_json::json;
 use serde::Serialize;
 use tower::Service;
-use hyper::{body::Bytes, Uri};
+use hyper::body::Bytes;
 
-use crate::extract::UriParams;
+use crate::extract::{CookieJar, Flash, FromParts, IntoResponseParts, UriParams};
 
 use crate::server::Handler;
+use super::{Describe, RouteKind};
 use crate::{BoxError, Body, Request, Response, ResponseShortcut};
 
+/// Only warn once that `CookieKeys` are missing for flash messages
+static MISSING_FLASH_KEYS: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone)]
 pub enum RenderError {
     MissingTemplate,
@@ -89,8 +93,24 @@ impl<T: TemplateEngine + 'static> Handler<TemplateRouter<T>> for TemplateRouter<
                 mime_guess::from_ext(ext.to_str().unwrap()).first().map(|mime| mime.to_string())
             });
 
+            // Flash messages are consumed when the template is rendered. They need `CookieKeys`
+            // in the router state, without them `flash` is always empty.
+            let (parts, body) = req.into_parts();
+            let jar = parts.extensions.get::<CookieJar>().cloned().unwrap_or_default();
+            let flash = match Flash::from_parts(&parts, jar).await {
+                Ok(flash) => Some(flash),
+                Err(err) => {
+                    if !MISSING_FLASH_KEYS.swap(true, Ordering::Relaxed) {
+                        log::warn!("({}) Templates don't get flash messages: {}", type_name::<T>(), err);
+                    }
+                    None
+                }
+            };
+            let req = Request::from_parts(parts, body);
+
             let data = json! ({
                 "captures": captures,
+                "flash": flash.as_ref().map(Flash::messages).unwrap_or_default(),
                 "request": json!({
                     "version": format!("{:?}", req.version()),
                     "method": req.method().to_string(),
@@ -111,8 +131,16 @@ impl<T: TemplateEngine + 'static> Handler<TemplateRouter<T>> for TemplateRouter<
                     if let Some(content_type) = content_type {
                         response = response.header(hyper::header::CONTENT_TYPE, content_type);
                     }
-                    response.body(result.into()).unwrap()
+                    let response = response.body(result.into()).unwrap();
+                    match flash {
+                        Some(flash) => {
+                            let Ok(response) = flash.into_response_parts(response);
+                            response
+                        },
+                        None => response,
+                    }
                 },
+                // The jar isn't sent, so flash messages aren't consumed by a page that failed
                 Err(err) => {
                     log::error!("({}) {}", type_name::<T>(), err);
                     match T::map_error(err) {
@@ -125,6 +153,12 @@ impl<T: TemplateEngine + 'static> Handler<TemplateRouter<T>> for TemplateRouter<
     }
 }
 
+impl<T> Describe for TemplateRouter<T> {
+    fn describe(&self) -> RouteKind {
+        RouteKind::Named("TemplateRouter")
+    }
+}
+
 impl<T: TemplateEngine + 'static, B> Service<Request<B>> for TemplateRouter<T>
 where
     B: HttpBody<Data = Bytes> + Send + 'static,
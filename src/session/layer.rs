@@ -0,0 +1,514 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use cookie::{Cookie, SameSite};
+use tower::{Layer, Service};
+
+use crate::{
+    extract::{CookieJar, FromParts, IntoResponse, IntoResponseParts},
+    HttpError, Request, Response,
+};
+
+use super::{Inner, Record, Session, SessionId, SessionStore};
+
+#[derive(Debug, Clone)]
+struct SessionConfig {
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    same_site: SameSite,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            name: "session".to_string(),
+            path: "/".to_string(),
+            domain: None,
+            secure: true,
+            same_site: SameSite::Lax,
+            idle_timeout: Some(Duration::from_secs(24 * 60 * 60)),
+            max_lifetime: None,
+        }
+    }
+}
+
+/// What has to happen with the session once the request is handled
+enum Action {
+    None,
+    Save(SessionId, Record),
+    Delete(Option<SessionId>),
+}
+
+impl SessionConfig {
+    async fn load<St: SessionStore>(&self, store: &St, jar: &CookieJar) -> crate::Result<Session> {
+        let Some(cookie) = jar.get(&self.name) else {
+            return Ok(Session::new());
+        };
+        let loaded = match SessionId::parse(cookie.value()) {
+            Some(id) => store.load(&id).await?.map(|record| (id, record)),
+            None => None,
+        };
+        match loaded {
+            Some((id, record)) if !record.is_expired() => Ok(Session::load(id, record)),
+            Some((id, _)) => {
+                store.delete(&id).await?;
+                self.remove_cookie(jar);
+                Ok(Session::new())
+            },
+            // Unknown ids are never reused, a new session gets a new id
+            None => {
+                self.remove_cookie(jar);
+                Ok(Session::new())
+            },
+        }
+    }
+
+    async fn save<St: SessionStore>(&self, store: &St, session: &Session, jar: &CookieJar) -> crate::Result<()> {
+        let now = SystemTime::now();
+        let (action, stale) = {
+            let mut inner = session.lock();
+            // The action depends on the stale ids, so they are taken afterwards
+            let action = self.action(&mut inner, now);
+            (action, std::mem::take(&mut inner.stale))
+        };
+
+        for id in &stale {
+            store.delete(id).await?;
+        }
+        match action {
+            Action::None => {},
+            Action::Save(id, record) => {
+                store.save(&id, &record).await?;
+                jar.add(self.cookie(&id, record.expires_at, now));
+            },
+            Action::Delete(id) => {
+                if let Some(id) = id {
+                    store.delete(&id).await?;
+                }
+                self.remove_cookie(jar);
+            },
+        }
+        Ok(())
+    }
+
+    fn action(&self, inner: &mut Inner, now: SystemTime) -> Action {
+        if inner.destroyed {
+            return Action::Delete(inner.id.take());
+        }
+
+        // Flashed values were shown, so the session changed even if nothing was inserted
+        let changed = inner.changed || !inner.flashed.is_empty();
+        if inner.data.is_empty() && inner.flash.is_empty() {
+            return match changed && (inner.id.is_some() || !inner.stale.is_empty()) {
+                true => Action::Delete(inner.id.take()),
+                false => Action::None,
+            };
+        }
+
+        // Sessions are only written when they change, or when the expiry moved by more than half
+        // of the idle timeout so active sessions don't expire
+        let expires_at = self.expires_at(inner.created_at, now);
+        let refresh = match (inner.expires_at, expires_at, self.idle_timeout) {
+            (Some(current), Some(expires_at), Some(idle)) => {
+                expires_at.duration_since(current).unwrap_or_default() > idle / 2
+            },
+            (current, expires_at, _) => current.is_some() != expires_at.is_some(),
+        };
+        if !changed && !refresh && inner.id.is_some() {
+            return Action::None;
+        }
+
+        inner.expires_at = expires_at;
+        inner.changed = false;
+        inner.flashed.clear();
+        let id = inner.id.get_or_insert_with(SessionId::generate).clone();
+        Action::Save(id, Record {
+            data: inner.data.clone(),
+            flash: inner.flash.clone(),
+            created_at: inner.created_at,
+            expires_at,
+        })
+    }
+
+    /// The idle timeout or the end of the lifetime, whichever comes first
+    fn expires_at(&self, created_at: SystemTime, now: SystemTime) -> Option<SystemTime> {
+        let idle = self.idle_timeout.map(|idle| now + idle);
+        let lifetime = self.max_lifetime.map(|lifetime| created_at + lifetime);
+        match (idle, lifetime) {
+            (Some(idle), Some(lifetime)) => Some(idle.min(lifetime)),
+            (idle, lifetime) => idle.or(lifetime),
+        }
+    }
+
+    fn cookie(&self, id: &SessionId, expires_at: Option<SystemTime>, now: SystemTime) -> Cookie<'static> {
+        let mut cookie = self.base_cookie(id.to_string());
+        if let Some(expires_at) = expires_at {
+            let max_age = expires_at.duration_since(now).unwrap_or_default().as_secs();
+            cookie.set_max_age(cookie::time::Duration::seconds(max_age as i64));
+        }
+        cookie
+    }
+
+    fn remove_cookie(&self, jar: &CookieJar) {
+        jar.remove(self.base_cookie(String::new()));
+    }
+
+    fn base_cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::build((self.name.clone(), value))
+            .path(self.path.clone())
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(self.same_site)
+            .build();
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie
+    }
+}
+
+/// Load the [`Session`] of every request from a store and save it once the request is handled.
+///
+/// By default the session cookie is called `session`, is `Secure`, `HttpOnly` and
+/// `SameSite=Lax`, and sessions expire after a day without requests.
+///
+/// ```ignore
+/// PathRouter::default()
+///     .route("/", get(index))
+///     .route_layer(
+///         SessionLayer::new(FileStore::new("sessions"))
+///             .with_idle_timeout(Duration::from_secs(30 * 60))
+///             .with_max_lifetime(Duration::from_secs(12 * 60 * 60))
+///     )
+/// ```
+#[derive(Debug)]
+pub struct SessionLayer<St> {
+    store: Arc<St>,
+    config: Arc<SessionConfig>,
+}
+
+impl<St> Clone for SessionLayer<St> {
+    fn clone(&self) -> Self {
+        Self { store: self.store.clone(), config: self.config.clone() }
+    }
+}
+
+impl<St: SessionStore> SessionLayer<St> {
+    pub fn new(store: St) -> Self {
+        Self {
+            store: Arc::new(store),
+            config: Arc::new(SessionConfig::default()),
+        }
+    }
+
+    fn config(mut self, f: impl FnOnce(&mut SessionConfig)) -> Self {
+        f(Arc::make_mut(&mut self.config));
+        self
+    }
+
+    pub fn with_cookie_name<S: Into<String>>(self, name: S) -> Self {
+        self.config(|config| config.name = name.into())
+    }
+
+    pub fn with_path<S: Into<String>>(self, path: S) -> Self {
+        self.config(|config| config.path = path.into())
+    }
+
+    pub fn with_domain<S: Into<String>>(self, domain: S) -> Self {
+        self.config(|config| config.domain = Some(domain.into()))
+    }
+
+    /// Only send the cookie over https, turn it off for local development over http
+    pub fn with_secure(self, secure: bool) -> Self {
+        self.config(|config| config.secure = secure)
+    }
+
+    pub fn with_same_site(self, same_site: SameSite) -> Self {
+        self.config(|config| config.same_site = same_site)
+    }
+
+    /// Expire sessions that aren't used for this long, `None` keeps them until the browser is
+    /// closed
+    pub fn with_idle_timeout<D: Into<Option<Duration>>>(self, timeout: D) -> Self {
+        self.config(|config| config.idle_timeout = timeout.into())
+    }
+
+    /// Expire sessions this long after they were created, even when they are used
+    pub fn with_max_lifetime<D: Into<Option<Duration>>>(self, lifetime: D) -> Self {
+        self.config(|config| config.max_lifetime = lifetime.into())
+    }
+}
+
+impl<S, St> Layer<S> for SessionLayer<St> {
+    type Service = SessionService<S, St>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        SessionService { store: self.store.clone(), config: self.config.clone(), service }
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionService<S, St> {
+    store: Arc<St>,
+    config: Arc<SessionConfig>,
+    service: S,
+}
+
+impl<S: Clone, St> Clone for SessionService<S, St> {
+    fn clone(&self) -> Self {
+        Self { store: self.store.clone(), config: self.config.clone(), service: self.service.clone() }
+    }
+}
+
+impl<S, St> Service<Request> for SessionService<S, St>
+where
+    S: Service<Request, Error = Infallible> + Clone + Send + 'static,
+    S::Response: IntoResponse,
+    S::Future: Send,
+    St: SessionStore,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let mut service = self.service.clone();
+        let store = self.store.clone();
+        let config = self.config.clone();
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+            // Share the jar with the handler and any other middleware
+            let jar = parts.extensions.get::<CookieJar>().cloned().unwrap_or_default();
+            let Ok(jar) = CookieJar::from_parts(&parts, jar).await;
+
+            let session = match config.load(&*store, &jar).await {
+                Ok(session) => session,
+                Err(e) => return Ok(HttpError::internal().with_source(e).into_response()),
+            };
+            parts.extensions.insert(jar.clone());
+            parts.extensions.insert(session.clone());
+
+            let response = service.call(Request::from_parts(parts, body)).await?.into_response();
+            if let Err(e) = config.save(&*store, &session, &jar).await {
+                return Ok(HttpError::internal().with_source(e).into_response());
+            }
+            let Ok(response) = jar.into_response_parts(response);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Map;
+
+    use super::*;
+    use crate::session::MemoryStore;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn config() -> SessionConfig {
+        SessionConfig { idle_timeout: Some(HOUR), ..SessionConfig::default() }
+    }
+
+    /// A session as it was loaded, with a value and it's expiry `expires_in` from now
+    fn loaded(expires_in: Duration) -> Session {
+        let now = SystemTime::now();
+        let mut data = Map::new();
+        data.insert("user".to_string(), "ann".into());
+        Session::load(SessionId::generate(), Record {
+            data,
+            flash: Map::new(),
+            created_at: now,
+            expires_at: Some(now + expires_in),
+        })
+    }
+
+    fn action(config: &SessionConfig, session: &Session) -> Action {
+        config.action(&mut session.lock(), SystemTime::now())
+    }
+
+    #[test]
+    fn unchanged_session_is_not_saved() {
+        let session = loaded(HOUR);
+        assert!(matches!(action(&config(), &session), Action::None));
+        assert!(matches!(action(&config(), &Session::new()), Action::None));
+    }
+
+    #[test]
+    fn changed_session_is_saved() {
+        let session = loaded(HOUR);
+        let id = session.id().unwrap();
+        session.insert("theme", "dark").unwrap();
+        match action(&config(), &session) {
+            Action::Save(saved, record) => {
+                assert_eq!(saved, id);
+                assert_eq!(record.data.len(), 2);
+            },
+            _ => panic!("expected the session to be saved"),
+        }
+        // Saving resets the change
+        assert!(matches!(action(&config(), &session), Action::None));
+    }
+
+    #[test]
+    fn new_session_gets_an_id() {
+        let session = Session::new();
+        session.insert("user", "ann").unwrap();
+        assert!(matches!(action(&config(), &session), Action::Save(..)));
+        assert!(session.id().is_some());
+    }
+
+    #[test]
+    fn refresh_after_half_the_idle_timeout() {
+        // Less than half of the idle timeout has passed
+        let session = loaded(HOUR - Duration::from_secs(60));
+        assert!(matches!(action(&config(), &session), Action::None));
+
+        let session = loaded(HOUR / 2 - Duration::from_secs(60));
+        assert!(matches!(action(&config(), &session), Action::Save(..)));
+    }
+
+    #[test]
+    fn no_refresh_past_the_max_lifetime() {
+        // The lifetime ends before the idle timeout, so refreshing doesn't move the expiry
+        let config = SessionConfig { max_lifetime: Some(HOUR / 4), ..config() };
+        let session = loaded(HOUR / 4);
+        assert!(matches!(action(&config, &session), Action::None));
+
+        session.insert("theme", "dark").unwrap();
+        match action(&config, &session) {
+            Action::Save(_, record) => {
+                assert!(record.expires_at.unwrap() <= record.created_at + HOUR / 4);
+            },
+            _ => panic!("expected the session to be saved"),
+        }
+    }
+
+    #[test]
+    fn destroyed_session_is_deleted() {
+        let session = loaded(HOUR);
+        let id = session.id().unwrap();
+        session.destroy();
+        assert!(matches!(action(&config(), &session), Action::Delete(None)));
+        assert_eq!(session.lock().stale, [id]);
+    }
+
+    #[test]
+    fn values_after_destroy_start_a_new_session() {
+        let session = loaded(HOUR);
+        let id = session.id().unwrap();
+        session.destroy();
+        session.flash("message", "You have been logged out").unwrap();
+        match action(&config(), &session) {
+            Action::Save(saved, record) => {
+                assert_ne!(saved, id);
+                assert!(record.data.is_empty());
+                assert_eq!(record.flash.len(), 1);
+            },
+            _ => panic!("expected a new session to be saved"),
+        }
+        assert_eq!(session.lock().stale, [id]);
+    }
+
+    #[test]
+    fn empty_rotated_session_removes_the_cookie() {
+        let session = loaded(HOUR);
+        session.clear();
+        session.rotate();
+        assert!(matches!(action(&config(), &session), Action::Delete(None)));
+    }
+
+    #[test]
+    fn cleared_session_is_deleted() {
+        let session = loaded(HOUR);
+        let id = session.id().unwrap();
+        session.clear();
+        assert!(matches!(action(&config(), &session), Action::Delete(Some(deleted)) if deleted == id));
+    }
+
+    #[test]
+    fn rotated_session_gets_a_new_id() {
+        let session = loaded(HOUR);
+        let id = session.id().unwrap();
+        session.rotate();
+        match action(&config(), &session) {
+            Action::Save(saved, record) => {
+                assert_ne!(saved, id);
+                assert_eq!(record.data.len(), 1);
+            },
+            _ => panic!("expected the session to be saved"),
+        }
+        assert_eq!(session.lock().stale, [id]);
+    }
+
+    #[test]
+    fn flashed_values_are_consumed() {
+        let now = SystemTime::now();
+        let mut flash = Map::new();
+        flash.insert("message".to_string(), "Saved".into());
+        let session = Session::load(SessionId::generate(), Record {
+            data: Map::from_iter([("user".to_string(), "ann".into())]),
+            flash,
+            created_at: now,
+            expires_at: Some(now + HOUR),
+        });
+        assert_eq!(session.flashed::<String>("message").as_deref(), Some("Saved"));
+        match action(&config(), &session) {
+            Action::Save(_, record) => assert!(record.flash.is_empty()),
+            _ => panic!("expected the consumed flash to be saved"),
+        }
+    }
+
+    #[tokio::test]
+    async fn lifecycle_with_a_store() {
+        let store = MemoryStore::default();
+        let config = config();
+
+        // Unknown ids aren't used, and their cookie is removed
+        let jar = CookieJar::default();
+        jar.with(|jar| jar.add_original(Cookie::new("session", SessionId::generate().to_string())));
+        let session = config.load(&store, &jar).await.unwrap();
+        assert!(session.id().is_none());
+        assert_eq!(jar.with(|jar| jar.delta().count()), 1);
+
+        session.insert("user", "ann").unwrap();
+        config.save(&store, &session, &jar).await.unwrap();
+        let id = session.id().unwrap();
+        assert_eq!(jar.get("session").unwrap().value(), id.as_str());
+
+        // Loaded again from the cookie
+        let jar = CookieJar::default();
+        jar.with(|jar| jar.add_original(Cookie::new("session", id.to_string())));
+        let session = config.load(&store, &jar).await.unwrap();
+        assert_eq!(session.get::<String>("user").as_deref(), Some("ann"));
+
+        // Rotating deletes the old id from the store
+        session.rotate();
+        config.save(&store, &session, &jar).await.unwrap();
+        assert!(store.load(&id).await.unwrap().is_none());
+        let rotated = session.id().unwrap();
+        assert!(store.load(&rotated).await.unwrap().is_some());
+
+        session.destroy();
+        config.save(&store, &session, &jar).await.unwrap();
+        assert!(store.load(&rotated).await.unwrap().is_none());
+        assert!(jar.get("session").is_none());
+        let removal = jar.with(|jar| jar.delta().find(|cookie| cookie.name() == "session").cloned()).unwrap();
+        assert_eq!(removal.max_age(), Some(cookie::time::Duration::ZERO));
+    }
+}
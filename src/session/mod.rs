@@ -0,0 +1,284 @@
+//! Server side sessions, identified by a cookie and kept in a [`SessionStore`].
+//!
+//! ```ignore
+//! PathRouter::default()
+//!     .route("/login", post(|session: Session, UrlEncoded(login): UrlEncoded<Login>| async move {
+//!         session.rotate();
+//!         session.insert("user", login.name)?;
+//!         Ok::<_, HttpError>(Redirect::see_other("/"))
+//!     }))
+//!     .route_layer(SessionLayer::new(MemoryStore::default()))
+//! ```
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use hyper::http::request::Parts;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    extract::{reject_setup, CookieJar, FromParts, IntoResponse, OptionalFromParts},
+    Response,
+};
+
+mod layer;
+mod store;
+
+pub use layer::{SessionLayer, SessionService};
+pub use store::{FileStore, MemoryStore, SessionStore};
+
+/// Random id of a session, sent to the client in the session cookie
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionId(String);
+
+impl SessionId {
+    pub(crate) fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().simple().to_string())
+    }
+
+    /// Only ids that could have been generated are accepted, so stores can safely use them as
+    /// file names or keys
+    pub fn parse(id: &str) -> Option<Self> {
+        let valid = id.len() == 32 && id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        valid.then(|| Self(id.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A session as it is kept by a [`SessionStore`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub data: Map<String, Value>,
+    /// Values for the next request only
+    pub flash: Map<String, Value>,
+    pub created_at: SystemTime,
+    /// `None` when the session lives until the browser is closed
+    pub expires_at: Option<SystemTime>,
+}
+
+impl Record {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= SystemTime::now())
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// `None` until the session is saved for the first time or after it is rotated
+    id: Option<SessionId>,
+    /// Ids that have to be deleted from the store
+    stale: Vec<SessionId>,
+    data: Map<String, Value>,
+    /// Flashed by this request
+    flash: Map<String, Value>,
+    /// Flashed by the previous request
+    flashed: Map<String, Value>,
+    created_at: SystemTime,
+    expires_at: Option<SystemTime>,
+    changed: bool,
+    destroyed: bool,
+}
+
+/// The session of the request, loaded by [`SessionLayer`].
+///
+/// Values are stored as json, so any type that can be serialized can be kept in a session. The
+/// session is only saved when it changes and isn't empty.
+#[derive(Debug, Clone)]
+pub struct Session(Arc<Mutex<Inner>>);
+
+impl Session {
+    fn new() -> Self {
+        Self::from_inner(None, Map::new(), Map::new(), SystemTime::now(), None)
+    }
+
+    fn load(id: SessionId, record: Record) -> Self {
+        Self::from_inner(Some(id), record.data, record.flash, record.created_at, record.expires_at)
+    }
+
+    fn from_inner(
+        id: Option<SessionId>,
+        data: Map<String, Value>,
+        flashed: Map<String, Value>,
+        created_at: SystemTime,
+        expires_at: Option<SystemTime>,
+    ) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            id,
+            stale: Vec::new(),
+            data,
+            flash: Map::new(),
+            flashed,
+            created_at,
+            expires_at,
+            changed: false,
+            destroyed: false,
+        })))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// `None` for a session that hasn't been saved yet
+    pub fn id(&self) -> Option<SessionId> {
+        self.lock().id.clone()
+    }
+
+    /// A value from the session, `None` when it is missing or isn't a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().data.get(key).cloned()?;
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.lock().data.contains_key(key)
+    }
+
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        let mut inner = self.lock();
+        inner.data.insert(key.to_string(), value);
+        inner.changed = true;
+        inner.destroyed = false;
+        Ok(())
+    }
+
+    /// Remove a value, it is returned when it is a `T`
+    pub fn remove<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let mut inner = self.lock();
+        let value = inner.data.remove(key)?;
+        inner.changed = true;
+        drop(inner);
+        serde_json::from_value(value).ok()
+    }
+
+    /// Remove every value, but keep the session
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.data.clear();
+        inner.flash.clear();
+        inner.changed = true;
+    }
+
+    /// Keep a value for the next request only, like a message to show after a redirect
+    pub fn flash<T: Serialize>(&self, key: &str, value: T) -> Result<(), serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        let mut inner = self.lock();
+        inner.flash.insert(key.to_string(), value);
+        inner.changed = true;
+        inner.destroyed = false;
+        Ok(())
+    }
+
+    /// A value flashed by the previous request
+    pub fn flashed<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().flashed.get(key).cloned()?;
+        serde_json::from_value(value).ok()
+    }
+
+    /// Give the session a new id and keep it's values. Call this when the user logs in or out so
+    /// an id that was known before can't be used to take over the session.
+    pub fn rotate(&self) {
+        let mut inner = self.lock();
+        if let Some(id) = inner.id.take() {
+            inner.stale.push(id);
+        }
+        inner.changed = true;
+    }
+
+    /// Delete the session from the store and the client. Values inserted or flashed afterwards
+    /// start a new session with a new id, like a message that the user was logged out.
+    pub fn destroy(&self) {
+        let mut inner = self.lock();
+        if let Some(id) = inner.id.take() {
+            inner.stale.push(id);
+        }
+        inner.data.clear();
+        inner.flash.clear();
+        inner.destroyed = true;
+    }
+}
+
+#[derive(Debug)]
+pub struct MissingSession;
+impl Display for MissingSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Missing session; make sure the route is wrapped with a `SessionLayer`")
+    }
+}
+impl std::error::Error for MissingSession {}
+
+impl IntoResponse for MissingSession {
+    fn into_response(self) -> Response {
+        reject_setup(self)
+    }
+}
+
+impl FromParts for Session {
+    type Rejection = MissingSession;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Session>().cloned().ok_or(MissingSession)
+    }
+}
+
+impl OptionalFromParts for Session {
+    type Rejection = Infallible;
+
+    async fn from_parts(parts: &Parts, _: CookieJar) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Session>().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_ids_parse() {
+        let id = SessionId::generate();
+        assert_eq!(SessionId::parse(id.as_str()), Some(id));
+        assert_ne!(SessionId::generate(), SessionId::generate());
+    }
+
+    #[test]
+    fn invalid_ids_are_rejected() {
+        for id in [
+            "",
+            "abc",
+            "../../../../../../../etc/passwd",
+            "0123456789abcdef0123456789abcde",
+            "0123456789abcdef0123456789abcdef0",
+            "0123456789ABCDEF0123456789ABCDEF",
+            "0123456789abcdef0123456789abcdeg",
+            "0123456789abcdef/123456789abcdef",
+        ] {
+            assert_eq!(SessionId::parse(id), None, "{id}");
+        }
+        assert!(SessionId::parse("0123456789abcdef0123456789abcdef").is_some());
+    }
+
+    #[test]
+    fn typed_values() {
+        let session = Session::new();
+        session.insert("count", 3).unwrap();
+        assert_eq!(session.get::<u32>("count"), Some(3));
+        assert_eq!(session.get::<String>("count"), None);
+        assert!(session.contains("count"));
+        assert_eq!(session.remove::<u32>("count"), Some(3));
+        assert!(!session.contains("count"));
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{Record, SessionId};
+
+/// Temporary files older than this are left over from a save that failed
+const TEMPORARY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where sessions are kept between requests
+pub trait SessionStore: Send + Sync + 'static {
+    /// `None` when there is no session with the id
+    fn load(&self, id: &SessionId) -> impl Future<Output = crate::Result<Option<Record>>> + Send;
+
+    fn save(&self, id: &SessionId, record: &Record) -> impl Future<Output = crate::Result<()>> + Send;
+
+    fn delete(&self, id: &SessionId) -> impl Future<Output = crate::Result<()>> + Send;
+}
+
+/// Sessions kept in memory, they are lost when the server stops and aren't shared between
+/// processes.
+///
+/// Expired sessions are removed when they are loaded, call [`MemoryStore::purge`] now and then to
+/// remove the ones that are never loaded again.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore(Arc<Mutex<HashMap<SessionId, Record>>>);
+
+impl MemoryStore {
+    /// Remove every expired session
+    pub fn purge(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).retain(|_, record| !record.is_expired());
+    }
+}
+
+impl SessionStore for MemoryStore {
+    async fn load(&self, id: &SessionId) -> crate::Result<Option<Record>> {
+        let mut sessions = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get(id) {
+            Some(record) if record.is_expired() => {
+                sessions.remove(id);
+                Ok(None)
+            },
+            record => Ok(record.cloned()),
+        }
+    }
+
+    async fn save(&self, id: &SessionId, record: &Record) -> crate::Result<()> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &SessionId) -> crate::Result<()> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        Ok(())
+    }
+}
+
+/// Sessions kept as json files in a directory, one file for every session.
+///
+/// Call [`FileStore::purge`] now and then to remove expired sessions that are never loaded again.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// The directory is created when the first session is saved
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, id: &SessionId) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Remove every expired session, and temporary files that were left behind
+    pub async fn purge(&self) -> crate::Result<()> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            // Temporary files of saves that didn't finish, recent ones might still be written
+            if path.extension().is_some_and(|extension| extension == "tmp") {
+                let modified = match entry.metadata().await {
+                    Ok(metadata) => metadata.modified()?,
+                    Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                if modified.elapsed().unwrap_or_default() > TEMPORARY_TIMEOUT {
+                    remove(&path).await?;
+                }
+                continue;
+            }
+            let is_session = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(SessionId::parse)
+                .is_some() && path.extension().is_some_and(|extension| extension == "json");
+            if !is_session {
+                continue;
+            }
+            let expired = match tokio::fs::read(&path).await {
+                Ok(bytes) => serde_json::from_slice::<Record>(&bytes).map_or(true, |record| record.is_expired()),
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if expired {
+                remove(&path).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn remove(path: &PathBuf) -> crate::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+impl SessionStore for FileStore {
+    async fn load(&self, id: &SessionId) -> crate::Result<Option<Record>> {
+        let path = self.path(id);
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        // A record that can't be decoded, from a corrupted file or an older format, is treated like
+        // an expired one instead of failing every request with that session cookie
+        match serde_json::from_slice::<Record>(&bytes) {
+            Ok(record) if !record.is_expired() => Ok(Some(record)),
+            _ => {
+                remove(&path).await?;
+                Ok(None)
+            },
+        }
+    }
+
+    async fn save(&self, id: &SessionId, record: &Record) -> crate::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Write to a temporary file first so a session is never read half written, every save has
+        // it's own so concurrent saves of a session don't collide
+        let path = self.path(id);
+        let temporary = self.dir.join(format!("{}.{}.tmp", id, uuid::Uuid::new_v4().simple()));
+        tokio::fs::write(&temporary, serde_json::to_vec(record)?).await?;
+        tokio::fs::rename(&temporary, &path).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &SessionId) -> crate::Result<()> {
+        remove(&self.path(id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use serde_json::Map;
+
+    use super::*;
+
+    fn record(expires_in: Option<Duration>) -> Record {
+        let now = SystemTime::now();
+        Record {
+            data: Map::from_iter([("user".to_string(), "ann".into())]),
+            flash: Map::from_iter([("message".to_string(), "Saved".into())]),
+            created_at: now,
+            expires_at: expires_in.map(|expires_in| now + expires_in),
+        }
+    }
+
+    fn expired() -> Record {
+        let mut record = record(None);
+        record.expires_at = Some(SystemTime::now() - Duration::from_secs(1));
+        record
+    }
+
+    async fn round_trip<St: SessionStore>(store: &St) {
+        let id = SessionId::generate();
+        assert!(store.load(&id).await.unwrap().is_none());
+
+        let saved = record(Some(Duration::from_secs(60)));
+        store.save(&id, &saved).await.unwrap();
+        let loaded = store.load(&id).await.unwrap().unwrap();
+        assert_eq!(loaded.data, saved.data);
+        assert_eq!(loaded.flash, saved.flash);
+        assert_eq!(loaded.created_at, saved.created_at);
+        assert_eq!(loaded.expires_at, saved.expires_at);
+
+        store.delete(&id).await.unwrap();
+        assert!(store.load(&id).await.unwrap().is_none());
+        // Deleting twice isn't an error
+        store.delete(&id).await.unwrap();
+
+        let id = SessionId::generate();
+        store.save(&id, &expired()).await.unwrap();
+        assert!(store.load(&id).await.unwrap().is_none());
+    }
+
+    fn directory() -> PathBuf {
+        std::env::temp_dir().join(format!("wayfinder-sessions-{}", uuid::Uuid::new_v4().simple()))
+    }
+
+    #[tokio::test]
+    async fn memory_store() {
+        let store = MemoryStore::default();
+        round_trip(&store).await;
+
+        store.save(&SessionId::generate(), &expired()).await.unwrap();
+        store.save(&SessionId::generate(), &record(None)).await.unwrap();
+        store.purge();
+        assert_eq!(store.0.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn file_store() {
+        let dir = directory();
+        let store = FileStore::new(&dir);
+        round_trip(&store).await;
+
+        let id = SessionId::generate();
+        store.save(&SessionId::generate(), &expired()).await.unwrap();
+        store.save(&id, &record(None)).await.unwrap();
+        store.purge().await.unwrap();
+        let files = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect::<Vec<_>>();
+        assert_eq!(files, [format!("{}.json", id)]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn undecodable_file_is_missing() {
+        let dir = directory();
+        let store = FileStore::new(&dir);
+        let id = SessionId::generate();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(store.path(&id), b"{\"data\":").unwrap();
+
+        assert!(store.load(&id).await.unwrap().is_none());
+        assert!(!store.path(&id).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_file_saves() {
+        let dir = directory();
+        let store = Arc::new(FileStore::new(&dir));
+        let id = SessionId::generate();
+        let saves = (0..16).map(|_| {
+            let (store, id) = (store.clone(), id.clone());
+            tokio::spawn(async move { store.save(&id, &record(None)).await.map_err(|e| e.to_string()) })
+        });
+        for save in saves.collect::<Vec<_>>() {
+            save.await.unwrap().unwrap();
+        }
+        assert!(store.load(&id).await.unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn purge_without_directory() {
+        FileStore::new(directory()).purge().await.unwrap();
+    }
+}
@@ -8,6 +8,9 @@ use quote::{ToTokens, TokenStreamExt};
 
 mod form_options;
 mod field_options;
+mod schema;
+mod route;
+mod typed_path;
 use form_options::FormOptions;
 use field_options::FieldOptions;
 
@@ -164,3 +167,35 @@ pub fn form_derive(input: TokenStream) -> TokenStream {
         }
     }.into()
 }
+
+#[proc_macro_error]
+#[proc_macro_derive(ToSchema, attributes(serde))]
+pub fn to_schema_derive(input: TokenStream) -> TokenStream {
+    let derive = parse_macro_input!(input as DeriveInput);
+    schema::derive(derive).into()
+}
+
+#[proc_macro_error]
+#[proc_macro_derive(TypedPath, attributes(typed_path))]
+pub fn typed_path_derive(input: TokenStream) -> TokenStream {
+    let derive = parse_macro_input!(input as DeriveInput);
+    typed_path::derive(derive).into()
+}
+
+macro_rules! route_attribute {
+    ($($method: ident),* $(,)?) => {
+        $(
+            /// Register an async fn as a route, mount it with `routes!`.
+            ///
+            /// Every named capture in the path has to be used by a `Capture` argument, captures
+            /// that aren't needed can be anonymous like `:_`.
+            #[proc_macro_error]
+            #[proc_macro_attribute]
+            pub fn $method(attr: TokenStream, item: TokenStream) -> TokenStream {
+                route::expand(stringify!($method), attr.into(), item.into()).into()
+            }
+        )*
+    };
+}
+
+route_attribute!(get, post, put, delete, patch, head, options, trace, connect);
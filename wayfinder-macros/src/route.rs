@@ -0,0 +1,188 @@
+use proc_macro_error::{abort, emit_error};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use syn::{spanned::Spanned, FnArg, GenericArgument, ItemFn, LitStr, Pat, PathArguments, Type};
+
+/// Names of the captures in a route pattern, in order. Anonymous `:_` captures are skipped since
+/// they can't be extracted.
+fn captures(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter_map(|part| part.strip_prefix(":*").or_else(|| part.strip_prefix(':')))
+        .filter(|name| *name != "_")
+        .map(str::to_string)
+        .collect()
+}
+
+/// The binding name of a pattern, without a leading underscore used to silence warnings
+fn binding(pat: &Pat) -> Option<String> {
+    match pat {
+        Pat::Ident(ident) => Some(ident.ident.to_string().trim_start_matches('_').to_string()),
+        _ => None,
+    }
+}
+
+/// The inner type of a `Capture<T>` argument
+fn capture_type(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) => path,
+        _ => return None,
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Capture" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Whether the type deserializes from a single capture, instead of a tuple, struct or map of them
+fn is_single_value(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => is_single_value(&reference.elem),
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+            const SINGLE: [&str; 18] = [
+                "bool", "char", "str", "String", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32",
+                "u64", "u128", "usize", "f32", "f64",
+            ];
+            SINGLE.contains(&segment.ident.to_string().as_str()) || segment.ident == "Uuid"
+        }),
+        _ => false,
+    }
+}
+
+/// Check that a `Capture` argument can be deserialized from the captures of the route pattern, and
+/// return the names of the captures it uses.
+///
+/// Only what is visible in the signature is checked: the arity of tuples, single values, and the
+/// names bound by the argument pattern. Captures read into other types are assumed to be used.
+fn check_capture(names: &[String], pat: &Pat, ty: &Type) -> Vec<String> {
+    let inner_pat = match pat {
+        Pat::TupleStruct(pat) if pat.elems.len() == 1 => pat.elems.first(),
+        _ => None,
+    };
+
+    if names.is_empty() {
+        emit_error!(ty.span(), "`Capture` is used but the route path has no captures");
+        return Vec::new();
+    }
+
+    if let Type::Tuple(tuple) = ty {
+        if tuple.elems.len() != names.len() {
+            emit_error!(
+                tuple.span(),
+                "expected a tuple of {} captures to match the route path, found {}",
+                names.len(),
+                tuple.elems.len()
+            );
+            return names.to_vec();
+        }
+    } else if is_single_value(ty) && names.len() > 1 {
+        emit_error!(
+            ty.span(),
+            "`Capture` binds a single value but the route path has {} captures, use a tuple or a struct",
+            names.len()
+        );
+        return names.to_vec();
+    }
+
+    match inner_pat {
+        Some(Pat::Ident(ident)) if !matches!(ty, Type::Tuple(_)) => {
+            let name = binding(&Pat::Ident(ident.clone())).unwrap();
+            if names.len() == 1 && names[0] != name {
+                emit_error!(ident.span(), "no capture named `{}` in the route path, expected `{}`", name, names[0]);
+            }
+        },
+        Some(Pat::Tuple(tuple)) => {
+            if tuple.elems.len() != names.len() {
+                emit_error!(tuple.span(), "expected {} captures to match the route path, found {}", names.len(), tuple.elems.len());
+                return names.to_vec();
+            }
+            for (elem, expected) in tuple.elems.iter().zip(names) {
+                if let Some(name) = binding(elem) {
+                    if !name.is_empty() && name != *expected {
+                        emit_error!(elem.span(), "capture `{}` is in position of `{}` in the route path", name, expected);
+                    }
+                }
+            }
+        },
+        Some(Pat::Struct(pat)) => {
+            let mut used = Vec::new();
+            for field in pat.fields.iter() {
+                let name = match &field.member {
+                    syn::Member::Named(ident) => ident.to_string(),
+                    syn::Member::Unnamed(_) => continue,
+                };
+                if !names.contains(&name) {
+                    emit_error!(field.span(), "no capture named `{}` in the route path", name);
+                }
+                used.push(name);
+            }
+            // Fields skipped with `..` could still be used
+            if pat.rest.is_none() {
+                return used;
+            }
+        },
+        _ => {},
+    }
+    names.to_vec()
+}
+
+pub fn expand(method: &str, attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
+    let path = match syn::parse2::<LitStr>(attr) {
+        Ok(path) => path,
+        Err(_) => abort!(Span::call_site(), "expected a route path, for example `#[{}(\"/users/:id\")]`", method),
+    };
+    let function = match syn::parse2::<ItemFn>(item) {
+        Ok(function) => function,
+        Err(err) => abort!(err.span(), "route attributes can only be used on functions"),
+    };
+
+    if function.sig.asyncness.is_none() {
+        emit_error!(function.sig.fn_token.span(), "route handlers must be async");
+    }
+    if !function.sig.generics.params.is_empty() {
+        emit_error!(function.sig.generics.span(), "route handlers can't be generic");
+    }
+
+    let names = captures(&path.value());
+    let mut used = Vec::new();
+    for arg in function.sig.inputs.iter() {
+        match arg {
+            FnArg::Typed(arg) => if let Some(ty) = capture_type(&arg.ty) {
+                used.extend(check_capture(&names, &arg.pat, ty));
+            },
+            FnArg::Receiver(receiver) => emit_error!(receiver.span(), "route handlers can't take `self`"),
+        }
+    }
+    for name in names.iter().filter(|name| !used.contains(name)) {
+        emit_error!(
+            path.span(),
+            "capture `{}` isn't used by a `Capture` argument, make it anonymous with `:_` if it isn't needed",
+            name
+        );
+    }
+
+    let vis = &function.vis;
+    let ident = &function.sig.ident;
+    let method = Ident::new(method, Span::call_site());
+
+    quote::quote! {
+        #function
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #ident {}
+
+        impl ::wayfinder::server::router::Mount for #ident {
+            const PATH: &'static str = #path;
+
+            fn mount(router: ::wayfinder::server::PathRouter) -> ::wayfinder::server::PathRouter {
+                router.route(#path, ::wayfinder::server::methods::#method(#ident))
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use proc_macro_error::emit_error;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, Meta};
+
+/// The subset of serde attributes that change how a type is serialized
+#[derive(Default)]
+struct SerdeOptions {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    skip: bool,
+    default: bool,
+}
+
+impl SerdeOptions {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    options.rename_all = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") || meta.path.is_ident("skip_deserializing") {
+                    options.skip = true;
+                } else if meta.path.is_ident("default") {
+                    options.default = true;
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<Expr>()?;
+                    }
+                } else if meta.input.peek(syn::Token![=]) {
+                    // Ignore options that don't change the schema
+                    meta.value()?.parse::<Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|_| Ok(()))?;
+                }
+                Ok(())
+            });
+
+            if let Err(err) = result {
+                emit_error!(attr.span(), "Invalid serde attribute: {}", err);
+            }
+        }
+        options
+    }
+}
+
+/// Apply a serde `rename_all` rule to a field or variant name
+fn rename(name: &str, rule: Option<&str>) -> String {
+    let words = split_words(name);
+    match rule {
+        Some("lowercase") => name.to_lowercase(),
+        Some("UPPERCASE") => name.to_uppercase(),
+        Some("snake_case") => words.join("_").to_lowercase(),
+        Some("SCREAMING_SNAKE_CASE") => words.join("_").to_uppercase(),
+        Some("kebab-case") => words.join("-").to_lowercase(),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        Some("PascalCase") => words.iter().map(|word| capitalize(word)).collect(),
+        Some("camelCase") => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| if i == 0 { word.to_lowercase() } else { capitalize(word) })
+            .collect(),
+        _ => name.to_string(),
+    }
+}
+
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in name.chars() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+        } else if c.is_uppercase() && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+            current.push(c);
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+/// Join the `///` doc comments of an item into a description
+fn description(attrs: &[Attribute]) -> TokenStream2 {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        quote::quote! { None }
+    } else {
+        let doc = lines.join("\n");
+        quote::quote! { Some(#doc) }
+    }
+}
+
+pub fn derive(derive: DeriveInput) -> TokenStream2 {
+    let ident = derive.ident.clone();
+    let options = SerdeOptions::parse(&derive.attrs);
+    let base = options.rename.clone().unwrap_or_else(|| ident.to_string());
+    // Generic types are named after their parameters, so `Page<User>` and `Page<Post>` don't
+    // overwrite each other in the components
+    let params = derive.generics.type_params().map(|param| &param.ident).collect::<Vec<_>>();
+    let name = quote::quote! {
+        ::wayfinder::openapi::__private::generic_name(#base, &[#(::std::any::type_name::<#params>()),*])
+    };
+    let doc = description(&derive.attrs);
+
+    let body = match derive.data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => {
+                let fields = fields.named.iter().filter_map(|field| {
+                    let field_options = SerdeOptions::parse(&field.attrs);
+                    if field_options.skip {
+                        return None;
+                    }
+
+                    let ty = &field.ty;
+                    let field_name = field_options.rename.clone().unwrap_or_else(|| {
+                        rename(&field.ident.as_ref().unwrap().to_string(), options.rename_all.as_deref())
+                    });
+                    let required = if field_options.default || options.default {
+                        quote::quote! { false }
+                    } else {
+                        quote::quote! { <#ty as ::wayfinder::openapi::ToSchema>::required() }
+                    };
+                    let doc = description(&field.attrs);
+                    Some(quote::quote! {
+                        (#field_name, <#ty as ::wayfinder::openapi::ToSchema>::schema(schemas), #required, #doc)
+                    })
+                }).collect::<Vec<_>>();
+
+                quote::quote! {
+                    let name = #name;
+                    if !::wayfinder::openapi::__private::register(schemas, &name) {
+                        return ::wayfinder::openapi::reference(&name);
+                    }
+                    let fields = vec![#(#fields),*];
+                    ::wayfinder::openapi::__private::object(schemas, &name, #doc, fields)
+                }
+            },
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed.first().unwrap().ty;
+                quote::quote! {
+                    <#ty as ::wayfinder::openapi::ToSchema>::schema(schemas)
+                }
+            },
+            fields => {
+                emit_error!(fields.span(), "ToSchema can only be derived for structs with named fields or newtypes");
+                return TokenStream2::new();
+            }
+        },
+        Data::Enum(data) => {
+            let mut variants = Vec::new();
+            for variant in data.variants.iter() {
+                if !matches!(variant.fields, Fields::Unit) {
+                    emit_error!(variant.span(), "ToSchema can only be derived for enums with unit variants");
+                    return TokenStream2::new();
+                }
+                let variant_options = SerdeOptions::parse(&variant.attrs);
+                if variant_options.skip {
+                    continue;
+                }
+                variants.push(variant_options.rename.unwrap_or_else(|| {
+                    rename(&variant.ident.to_string(), options.rename_all.as_deref())
+                }));
+            }
+
+            quote::quote! {
+                let name = #name;
+                ::wayfinder::openapi::__private::string_enum(schemas, &name, #doc, &[#(#variants),*])
+            }
+        },
+        Data::Union(_) => {
+            emit_error!(Span::call_site(), "ToSchema can't be derived for unions");
+            return TokenStream2::new();
+        }
+    };
+
+    let mut generics = derive.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(::wayfinder::openapi::ToSchema));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote::quote! {
+        impl #impl_generics ::wayfinder::openapi::ToSchema for #ident #ty_generics #where_clause {
+            fn schema(schemas: &mut ::wayfinder::openapi::Schemas) -> ::wayfinder::openapi::__private::Value {
+                #body
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use proc_macro_error::{abort, emit_error};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{spanned::Spanned, Data, DeriveInput, Fields, Index, LitStr, Member};
+
+enum Segment {
+    Literal(String),
+    Capture(String, bool),
+}
+
+/// Characters RFC 3986 allows in a path segment, besides percent encoded ones
+fn is_path_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~!$&'()*+,;=:@%".contains(c)
+}
+
+fn segments(path: &LitStr) -> Vec<Segment> {
+    let value = path.value();
+    if !value.starts_with('/') {
+        emit_error!(path.span(), "typed paths must start with `/`");
+    }
+
+    value.split('/').map(|part| {
+        let (name, catch_all) = match part.strip_prefix(":*") {
+            Some(name) => (name, true),
+            None => match part.strip_prefix(':') {
+                Some(name) => (name, false),
+                None => {
+                    if let Some(c) = part.chars().find(|c| !is_path_char(*c)) {
+                        emit_error!(path.span(), "`{}` has to be percent encoded in typed paths", c);
+                    }
+                    return Segment::Literal(part.to_string());
+                },
+            },
+        };
+        if name == "_" {
+            emit_error!(path.span(), "anonymous captures can't be used in typed paths, give the capture a name");
+        }
+        Segment::Capture(name.to_string(), catch_all)
+    }).collect()
+}
+
+pub fn derive(derive: DeriveInput) -> TokenStream2 {
+    let ident = derive.ident.clone();
+    let path = match derive.attrs.iter().find(|attr| attr.path().is_ident("typed_path")) {
+        Some(attr) => match attr.parse_args::<LitStr>() {
+            Ok(path) => path,
+            Err(err) => abort!(err.span(), "expected a route path, for example `#[typed_path(\"/users/:id\")]`"),
+        },
+        None => abort!(Span::call_site(), "missing the route path, add `#[typed_path(\"/users/:id\")]`"),
+    };
+
+    let segments = segments(&path);
+    let captures = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Capture(name, _) => Some(name.clone()),
+            Segment::Literal(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    // Map every capture to the field it is read from
+    let members: Vec<Member> = match &derive.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| field.ident.clone().unwrap()).collect::<Vec<_>>();
+                for name in names.iter() {
+                    if !captures.contains(&name.to_string()) {
+                        emit_error!(name.span(), "field `{}` is not a capture in `{}`", name, path.value());
+                    }
+                }
+                captures.iter().map(|capture| match names.iter().find(|name| *name == capture) {
+                    Some(name) => Member::Named(name.clone()),
+                    None => abort!(path.span(), "capture `{}` has no matching field", capture),
+                }).collect()
+            },
+            Fields::Unnamed(fields) => {
+                if fields.unnamed.len() != captures.len() {
+                    abort!(
+                        fields.span(),
+                        "expected {} fields to match the captures in `{}`, found {}",
+                        captures.len(),
+                        path.value(),
+                        fields.unnamed.len()
+                    );
+                }
+                (0..captures.len()).map(|i| Member::Unnamed(Index::from(i))).collect()
+            },
+            Fields::Unit => {
+                if !captures.is_empty() {
+                    abort!(path.span(), "unit structs can only be used for paths without captures");
+                }
+                Vec::new()
+            },
+        },
+        _ => abort!(Span::call_site(), "TypedPath can only be derived for structs"),
+    };
+
+    let mut members = members.into_iter();
+    let writes = segments.iter().enumerate().map(|(i, segment)| {
+        let separator = (i > 0).then(|| quote::quote! { f.write_str("/")?; });
+        let segment = match segment {
+            Segment::Literal(literal) => quote::quote! { f.write_str(#literal)?; },
+            Segment::Capture(_, catch_all) => {
+                let member = members.next().unwrap();
+                quote::quote! { f.write_str(&::wayfinder::extract::__encode(&self.#member, #catch_all))?; }
+            },
+        };
+        quote::quote! { #separator #segment }
+    }).collect::<Vec<_>>();
+
+    let (impl_generics, ty_generics, where_clause) = derive.generics.split_for_impl();
+
+    quote::quote! {
+        impl #impl_generics ::wayfinder::extract::TypedPath for #ident #ty_generics #where_clause {
+            const PATH: &'static str = #path;
+        }
+
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                #(#writes)*
+                Ok(())
+            }
+        }
+
+        impl #impl_generics ::wayfinder::extract::FromParts for #ident #ty_generics #where_clause {
+            type Rejection = ::wayfinder::extract::PathRejection;
+
+            async fn from_parts(
+                parts: &::wayfinder::extract::Parts,
+                jar: ::wayfinder::extract::CookieJar,
+            ) -> ::std::result::Result<Self, Self::Rejection> {
+                <::wayfinder::extract::Capture<Self> as ::wayfinder::extract::FromParts>::from_parts(parts, jar)
+                    .await
+                    .map(|capture| capture.0)
+            }
+        }
+
+        impl #impl_generics ::wayfinder::openapi::OperationInput for #ident #ty_generics #where_clause {}
+    }
+}